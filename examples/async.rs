@@ -0,0 +1,49 @@
+use std::{
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake},
+    thread::{self, Thread},
+};
+
+use cronet_rs::client::{AsyncClient, Body};
+
+fn main() {
+    let client = AsyncClient::new();
+
+    println!("sending GET request...");
+    let request = http::Request::builder()
+        .method("GET")
+        .uri("https://httpbin.org/anything")
+        .body(Body::default())
+        .unwrap();
+    match block_on(client.send(request)) {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            let body = response.body().as_bytes().unwrap();
+            println!("Body: {}", String::from_utf8_lossy(body));
+        }
+        Err(error) => println!("Error: {}", error),
+    }
+}
+
+/// Minimal executor so this example doesn't depend on an async runtime.
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::{marker::PhantomData, mem::ManuallyDrop, ops::Deref};
+
+/// A native object that is owned by someone else, e.g. by Cronet for the duration of a callback
+/// or by the object it was obtained from.
+///
+/// It can be used like the owned type, but is not destroyed when dropped.
+pub struct Borrowed<'a, T> {
+    inner: ManuallyDrop<T>,
+    owner: PhantomData<&'a ()>,
+}
+
+impl<T> Borrowed<'_, T> {
+    /// Wraps `inner` without taking ownership of the native object it points to.
+    ///
+    /// # Safety
+    ///
+    /// The native object must stay alive for the lifetime of the returned value.
+    pub(crate) unsafe fn new(inner: T) -> Self {
+        Self {
+            inner: ManuallyDrop::new(inner),
+            owner: PhantomData,
+        }
+    }
+}
+
+impl<T> Deref for Borrowed<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::Borrowed;
+
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn it_does_not_drop_the_borrowed_value() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        {
+            let borrowed = unsafe { Borrowed::new(Counted(drops.clone())) };
+            assert_eq!(Arc::strong_count(&borrowed.0), 2);
+        }
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+    }
+}
@@ -1,9 +1,9 @@
-use std::slice;
+use std::{mem, ptr::NonNull, slice};
 
 use crate::{
-    BufferCallback, Cronet_BufferPtr, Cronet_Buffer_Create, Cronet_Buffer_Destroy,
+    Borrowed, BufferCallback, Cronet_BufferPtr, Cronet_Buffer_Create, Cronet_Buffer_Destroy,
     Cronet_Buffer_GetData, Cronet_Buffer_GetSize, Cronet_Buffer_InitWithAlloc,
-    Cronet_Buffer_InitWithDataAndCallback, Cronet_RawDataPtr, Destroy,
+    Cronet_Buffer_InitWithDataAndCallback, Cronet_RawDataPtr,
 };
 
 pub struct Buffer {
@@ -25,7 +25,7 @@ impl Buffer {
         buffer
     }
 
-    pub fn new_with_data_and_callback<T>(
+    pub fn new_with_data_and_callback<T: Send + 'static>(
         data: Box<T>,
         size: u64,
         callback: BufferCallback,
@@ -41,14 +41,23 @@ impl Buffer {
         }
     }
 
-    pub fn init_data_and_callback<T>(&self, data: Box<T>, size: u64, callback: BufferCallback) {
+    /// Initializes the buffer with the given data.
+    /// The buffer takes ownership of `data` and `callback`.
+    /// When the buffer is destroyed, `callback` is invoked and `data` is dropped afterwards.
+    pub fn init_data_and_callback<T: Send + 'static>(
+        &self,
+        data: Box<T>,
+        size: u64,
+        callback: BufferCallback,
+    ) {
+        let dataPtr = Box::into_raw(data);
+        let callback = callback.dropping(dataPtr);
         unsafe {
-            let dataPtr = Box::into_raw(data);
             Cronet_Buffer_InitWithDataAndCallback(
                 self.ptr,
                 dataPtr as Cronet_RawDataPtr,
                 size,
-                callback.ptr,
+                callback.into_raw(),
             );
         }
     }
@@ -61,18 +70,48 @@ impl Buffer {
         unsafe { Cronet_Buffer_GetData(self.ptr) }
     }
 
-    pub fn data<T>(&self) -> Box<T> {
-        unsafe {
-            let dataPtr: Cronet_RawDataPtr = self.data_ptr();
-            Box::from_raw(dataPtr as *mut T)
+    /// Returns the data of the buffer as a value of type `T`, which stays owned by the buffer.
+    ///
+    /// Panics if a `T` doesn't fit into the buffer or if the data isn't aligned for `T`.
+    pub fn data<T>(&self) -> &T {
+        unsafe { &*self.checked_data_ptr(1) }
+    }
+
+    /// Returns a pointer to the first `len` values of type `T` in the buffer.
+    ///
+    /// Panics if they don't fit into the buffer or if the data isn't aligned for `T`.
+    fn checked_data_ptr<T>(&self, len: usize) -> *mut T {
+        let fits = len
+            .checked_mul(mem::size_of::<T>())
+            .is_some_and(|bytes| bytes as u64 <= self.size());
+        assert!(fits, "{} values don't fit into the buffer", len);
+        if len == 0 {
+            return NonNull::dangling().as_ptr();
         }
+        let ptr = self.data_ptr() as *mut T;
+        assert!(ptr.is_aligned(), "buffer data is not aligned");
+        ptr
     }
 
+    /// Returns the data of the buffer as a slice of the given length.
+    ///
+    /// Panics if the slice doesn't fit into the buffer.
     pub fn data_slice<T>(&self, size: usize) -> &[T] {
-        unsafe {
-            let slice = slice::from_raw_parts(self.data_ptr() as *mut T, size);
-            slice
-        }
+        unsafe { slice::from_raw_parts(self.checked_data_ptr(size), size) }
+    }
+
+    /// Returns the data of the buffer as a mutable slice of the given length.
+    ///
+    /// Panics if the slice doesn't fit into the buffer.
+    pub fn data_slice_mut<T>(&mut self, size: usize) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.checked_data_ptr(size), size) }
+    }
+
+    /// Gives up ownership of the native buffer, e.g. to a [crate::UrlRequest].
+    pub(crate) fn into_raw(self) -> Cronet_BufferPtr {
+        let ptr = self.ptr;
+        mem::forget(self);
+        ptr
     }
 
     /// Write arbitrary data to the buffer.
@@ -94,13 +133,16 @@ impl Buffer {
             return Err("Buffer is too small to hold the specified data");
         }
 
+        // The replaced data ends up in `src`, which is dropped.
         unsafe {
             std::ptr::swap(src, dst);
+            drop(Box::from_raw(src));
         }
 
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub(crate) fn write_slice<T>(&self, data: &[T], data_size: u64) -> Result<(), &'static str> {
         let src = data.as_ptr();
         let src_size = data_size;
@@ -119,8 +161,18 @@ impl Buffer {
     }
 }
 
-impl Destroy for Buffer {
-    fn destroy(&self) {
+impl Borrowed<'_, Buffer> {
+    /// Returns the data of the borrowed buffer as a mutable slice of the given size,
+    /// e.g. to fill the buffer that Cronet passes to [crate::UploadDataProviderHandler::read].
+    ///
+    /// Panics if the slice doesn't fit into the buffer.
+    pub fn data_slice_mut<T>(&mut self, size: usize) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.checked_data_ptr(size), size) }
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
         unsafe {
             Cronet_Buffer_Destroy(self.ptr);
         }
@@ -135,20 +187,17 @@ impl Default for Buffer {
 
 #[cfg(test)]
 mod tests {
-    use crate::Destroy;
 
     #[test]
     fn it_creates_empty_buffer() {
         let buffer = super::Buffer::new();
         assert_eq!(buffer.size(), 0);
-        buffer.destroy();
     }
 
     #[test]
     fn it_creates_buffer() {
         let buffer = super::Buffer::new_with_size(10);
         assert_eq!(buffer.size(), 10);
-        buffer.destroy();
     }
 
     #[test]
@@ -158,28 +207,68 @@ mod tests {
         let buffer = super::Buffer::new_with_data_and_callback(
             Box::new(data),
             data.len() as u64,
-            super::BufferCallback::new(|self_, buffer| {
+            super::BufferCallback::new(|_, buffer| {
                 assert_eq!(buffer.size(), 5);
-                self_.destroy(); // destroy the callback itself
             }),
         );
         assert_eq!(buffer.size(), data.len() as u64);
         assert_eq!(*buffer.data::<[u8; 5]>(), data);
-        buffer.destroy();
 
         // String buffer
         let data = String::from("test");
+        let size = std::mem::size_of::<String>() as u64;
         let buffer = super::Buffer::new_with_data_and_callback(
             Box::new(data.clone()),
-            data.len() as u64,
-            super::BufferCallback::new(|self_, buffer| {
-                assert_eq!(buffer.size(), 4);
-                self_.destroy(); // destroy the callback itself
+            size,
+            super::BufferCallback::new(move |_, buffer| {
+                assert_eq!(buffer.size(), size);
             }),
         );
-        assert_eq!(buffer.size(), data.len() as u64);
+        assert_eq!(buffer.size(), size);
         assert_eq!(*buffer.data::<String>(), data);
-        buffer.destroy();
+    }
+
+    #[test]
+    fn it_drops_the_data_with_the_buffer() {
+        use std::sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc,
+        };
+
+        struct Counted(Arc<AtomicUsize>);
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        let destroyed = Arc::new(AtomicBool::new(false));
+        let on_destroy = destroyed.clone();
+        let buffer = super::Buffer::new_with_data_and_callback(
+            Box::new(Counted(drops.clone())),
+            std::mem::size_of::<Counted>() as u64,
+            super::BufferCallback::new(move |_, _| on_destroy.store(true, Ordering::SeqCst)),
+        );
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+        drop(buffer);
+        assert!(destroyed.load(Ordering::SeqCst));
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn it_checks_the_size_of_data_slices() {
+        let mut buffer = super::Buffer::new_with_size(4);
+        assert_eq!(buffer.data_slice_mut::<u8>(4).len(), 4);
+        assert_eq!(buffer.data_slice::<u16>(2).len(), 2);
+        assert!(super::Buffer::new().data_slice::<u8>(0).is_empty());
+
+        let result = std::panic::catch_unwind(|| buffer.data_slice::<u8>(5).len());
+        assert!(result.is_err());
+        let result = std::panic::catch_unwind(|| buffer.data_slice::<u32>(2).len());
+        assert!(result.is_err());
     }
 
     #[test]
@@ -196,6 +285,14 @@ mod tests {
         assert!(result.is_ok());
         let actual = *buffer.data::<[u8; 5]>();
         assert_eq!(actual, expected);
-        buffer.destroy();
+    }
+
+    #[test]
+    fn it_checks_the_size_of_data() {
+        let buffer = super::Buffer::new_with_size(4);
+        assert_eq!(buffer.data::<[u8; 4]>().len(), 4);
+
+        let result = std::panic::catch_unwind(|| buffer.data::<[u8; 5]>().len());
+        assert!(result.is_err());
     }
 }
@@ -1,49 +1,87 @@
-use once_cell::sync::Lazy;
+use std::{mem, sync::Mutex};
 
 use crate::{
-    state::CronetCallbacks, Buffer, Cronet_BufferCallbackPtr, Cronet_BufferCallback_CreateWith,
-    Cronet_BufferCallback_Destroy, Cronet_BufferPtr, Destroy,
+    client_context, unwind, Borrowed, Buffer, Cronet_BufferCallbackPtr,
+    Cronet_BufferCallback_CreateWith, Cronet_BufferCallback_Destroy,
+    Cronet_BufferCallback_GetClientContext, Cronet_BufferCallback_SetClientContext,
+    Cronet_BufferPtr,
 };
 
-static mut BUFFER_CALLBACKS: Lazy<CronetCallbacks<Cronet_BufferCallbackPtr, BufferCallbackFn>> =
-    Lazy::new(CronetCallbacks::new);
+type BufferCallbackHandler = Mutex<Box<BufferCallbackFn>>;
 
 #[no_mangle]
 unsafe extern "C" fn cronetBufferCallbackOnDestroy(
     selfPtr: Cronet_BufferCallbackPtr,
     bufferPtr: Cronet_BufferPtr,
 ) {
-    let mut lockedMap = BUFFER_CALLBACKS.map().lock().unwrap();
-    lockedMap.remove(&selfPtr);
-    if let Some(callback) = lockedMap.get(&selfPtr) {
-        callback(BufferCallback { ptr: selfPtr }, Buffer { ptr: bufferPtr })
+    // The callback is owned by its buffer (see `Buffer::init_data_and_callback`), which is now destroyed.
+    let callback = BufferCallback { ptr: selfPtr };
+    let handler = client_context::get::<BufferCallbackHandler>(
+        Cronet_BufferCallback_GetClientContext(selfPtr),
+    );
+    if let Some(handler) = handler {
+        // The buffer is destroyed regardless, so a panic is ignored.
+        let _ = unwind::catch(|| {
+            let mut handler = unwind::lock(&handler);
+            handler(
+                Borrowed::new(BufferCallback { ptr: selfPtr }),
+                Borrowed::new(Buffer { ptr: bufferPtr }),
+            );
+        });
     }
+    drop(callback);
 }
 
-pub type BufferCallbackFn = fn(callback: BufferCallback, buffer: Buffer);
+pub type BufferCallbackFn = dyn FnMut(Borrowed<'_, BufferCallback>, Borrowed<'_, Buffer>) + Send;
 
 /// Callback passed to `Buffer::initWithDataAndCallback` that gets invoked when the related `Buffer` is destroyed.
+///
+/// The buffer takes ownership of the callback, which is destroyed right after it was invoked.
 pub struct BufferCallback {
     pub(crate) ptr: Cronet_BufferCallbackPtr,
 }
 
 impl BufferCallback {
-    pub fn new(on_destroy: BufferCallbackFn) -> Self {
+    pub fn new<F>(on_destroy: F) -> Self
+    where
+        F: FnMut(Borrowed<'_, BufferCallback>, Borrowed<'_, Buffer>) + Send + 'static,
+    {
         unsafe {
             let ptr = Cronet_BufferCallback_CreateWith(Some(cronetBufferCallbackOnDestroy));
-            BUFFER_CALLBACKS
-                .map()
-                .lock()
-                .unwrap()
-                .insert(ptr, on_destroy);
+            let handler: BufferCallbackHandler = Mutex::new(Box::new(on_destroy));
+            Cronet_BufferCallback_SetClientContext(ptr, client_context::into_raw(handler));
             BufferCallback { ptr }
         }
     }
+
+    /// Returns a callback that invokes this callback and then drops `data`,
+    /// which was passed to a [Buffer] that doesn't free it on its own.
+    pub(crate) fn dropping<T: Send + 'static>(self, data: *mut T) -> Self {
+        // Raw pointers aren't `Send`, but the callback only uses them once the buffer is destroyed.
+        let callback = self.into_raw() as usize;
+        let mut data = Some(data as usize);
+        BufferCallback::new(move |_, buffer| unsafe {
+            cronetBufferCallbackOnDestroy(callback as Cronet_BufferCallbackPtr, buffer.ptr);
+            if let Some(data) = data.take() {
+                drop(Box::from_raw(data as *mut T));
+            }
+        })
+    }
+
+    /// Gives up ownership of the native callback, e.g. to a [Buffer].
+    pub(crate) fn into_raw(self) -> Cronet_BufferCallbackPtr {
+        let ptr = self.ptr;
+        mem::forget(self);
+        ptr
+    }
 }
 
-impl Destroy for BufferCallback {
-    fn destroy(&self) {
+impl Drop for BufferCallback {
+    fn drop(&mut self) {
         unsafe {
+            client_context::release::<BufferCallbackHandler>(
+                Cronet_BufferCallback_GetClientContext(self.ptr),
+            );
             Cronet_BufferCallback_Destroy(self.ptr);
         }
     }
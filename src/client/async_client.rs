@@ -0,0 +1,208 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use super::{Body, Client, ClientError, RequestHandle, Status, StatusSink};
+#[cfg(feature = "tower")]
+use super::{Limit, Permit, DEFAULT_MAX_IN_FLIGHT};
+
+/// Asynchronous variant of [Client].
+///
+/// Requests are driven by the Cronet callbacks directly,
+/// so awaiting a response never blocks the calling thread.
+pub struct AsyncClient {
+    client: Client,
+    #[cfg(feature = "tower")]
+    pub(crate) limit: Arc<Limit>,
+    /// Slot reserved by `poll_ready` for the next request.
+    #[cfg(feature = "tower")]
+    pub(crate) permit: Option<Permit>,
+}
+
+impl AsyncClient {
+    pub fn new() -> Self {
+        Self::from(Client::new())
+    }
+
+    /// Sets the maximum number of requests that are sent concurrently through [tower_service::Service].
+    /// The default is [DEFAULT_MAX_IN_FLIGHT].
+    #[cfg(feature = "tower")]
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        self.limit = Limit::new(max);
+        self
+    }
+
+    /// Returns the underlying blocking [Client].
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Sends the request and returns a future that resolves to its response.
+    ///
+    /// Dropping the future before it completes cancels the request.
+    pub fn send(&self, request: http::Request<Body>) -> ResponseFuture {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let sink = FutureSink {
+            shared: shared.clone(),
+        };
+        match self.client.start(request, sink, false) {
+            Ok(handle) => ResponseFuture {
+                shared,
+                handle: Some(handle),
+                error: None,
+            },
+            Err(error) => ResponseFuture {
+                shared,
+                handle: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+/// Clones share the underlying [Client] and the limit of requests in flight.
+impl Clone for AsyncClient {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            #[cfg(feature = "tower")]
+            limit: self.limit.clone(),
+            // Slots are reserved per clone.
+            #[cfg(feature = "tower")]
+            permit: None,
+        }
+    }
+}
+
+impl From<Client> for AsyncClient {
+    fn from(client: Client) -> Self {
+        Self {
+            client,
+            #[cfg(feature = "tower")]
+            limit: Limit::new(DEFAULT_MAX_IN_FLIGHT),
+            #[cfg(feature = "tower")]
+            permit: None,
+        }
+    }
+}
+
+impl Default for AsyncClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    status: Option<Status>,
+    waker: Option<Waker>,
+}
+
+/// Stores the final [Status] of a request and wakes the task awaiting it.
+struct FutureSink {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl StatusSink for FutureSink {
+    fn complete(&mut self, status: Status) {
+        let waker = {
+            let mut shared = self.shared.lock().unwrap();
+            shared.status = Some(status);
+            shared.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Future returned by [AsyncClient::send].
+pub struct ResponseFuture {
+    shared: Arc<Mutex<Shared>>,
+    handle: Option<RequestHandle>,
+    error: Option<ClientError>,
+}
+
+impl Future for ResponseFuture {
+    type Output = Result<http::Response<Body>, ClientError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(error) = self.error.take() {
+            return Poll::Ready(Err(error));
+        }
+
+        let mut shared = self.shared.lock().unwrap();
+        match shared.status.take() {
+            Some(status) => Poll::Ready(status.into_result()),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for ResponseFuture {
+    fn drop(&mut self) {
+        // No-op if the request already finished.
+        if let Some(handle) = &self.handle {
+            handle.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        task::{Context, Poll, Wake, Waker},
+    };
+
+    use crate::client::{ClientError, Status, StatusSink};
+
+    use super::{FutureSink, ResponseFuture, Shared};
+
+    struct FlagWaker(AtomicBool);
+
+    impl Wake for FlagWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn it_wakes_the_future_on_completion() {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let mut sink = FutureSink {
+            shared: shared.clone(),
+        };
+        let mut future = ResponseFuture {
+            shared,
+            handle: None,
+            error: None,
+        };
+
+        let flag = Arc::new(FlagWaker(AtomicBool::new(false)));
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+        assert!(!flag.0.load(Ordering::SeqCst));
+
+        sink.complete(Status::Canceled);
+        assert!(flag.0.load(Ordering::SeqCst));
+
+        match Pin::new(&mut future).poll(&mut cx) {
+            Poll::Ready(Err(ClientError::CancellationError)) => {}
+            other => panic!("unexpected poll result: {:?}", other),
+        }
+    }
+}
@@ -1,26 +1,35 @@
 // This implementation is inspired by: https://github.com/seanmonstar/reqwest/blob/6792f697fcdb27c47dcbf7bd05f23368d1d4ac80/src/blocking/body.rs
 // License: https://github.com/seanmonstar/reqwest/blob/master/LICENSE-MIT
 
-use bytes::{Bytes, BytesMut};
+use bytes::{Buf, Bytes, BytesMut};
 use std::fmt;
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex};
+
+/// Function that returns a new body with the original data, used to rewind an upload.
+pub type RewindFn = dyn FnMut() -> io::Result<Body> + Send;
+
+/// Rewind function of a body that is shared with others that send the body again.
+pub(crate) type SharedRewind = Arc<Mutex<Box<RewindFn>>>;
 
-#[derive(Debug)]
 pub struct Body {
     kind: Kind,
+    rewind: Option<Box<RewindFn>>,
 }
 
 impl Body {
     pub fn new<R: Read + Send + 'static>(reader: R) -> Body {
         Body {
             kind: Kind::Reader(Box::from(reader), None),
+            rewind: None,
         }
     }
 
     pub fn sized<R: Read + Send + 'static>(reader: R, len: u64) -> Body {
         Body {
             kind: Kind::Reader(Box::from(reader), Some(len)),
+            rewind: None,
         }
     }
 
@@ -53,9 +62,56 @@ impl Body {
         }
     }
 
+    /// Sets the function that is used to rewind the body when it is uploaded.
+    ///
+    /// Rewinding is required to follow redirects that preserve the request body (307 and 308)
+    /// and for retries when the server times out stale sockets.
+    /// Bodies that are buffered in memory or read from a [File] are rewindable by default,
+    /// so this is only needed for bodies created with [Body::new] or [Body::sized].
+    pub fn with_rewind<F>(mut self, rewind: F) -> Body
+    where
+        F: FnMut() -> io::Result<Body> + Send + 'static,
+    {
+        self.rewind = Some(Box::new(rewind));
+        self
+    }
+
+    /// Takes the function that rewinds the body, if it is rewindable.
+    /// For buffered bodies, the function returns a copy of the current data.
+    pub(crate) fn take_rewind(&mut self) -> Option<Box<RewindFn>> {
+        if let Some(rewind) = self.rewind.take() {
+            return Some(rewind);
+        }
+
+        let bytes = match self.kind {
+            Kind::Reader(..) => return None,
+            Kind::Bytes(ref bytes) => bytes.clone(),
+            Kind::BytesMut(ref bytes) => bytes.clone().freeze(),
+        };
+        Some(Box::new(move || Ok(Body::from(bytes.clone()))))
+    }
+
+    /// Shares the function that rewinds the body, if it is rewindable.
+    /// The body itself is still rewound through the shared function.
+    pub(crate) fn share_rewind(&mut self) -> Option<SharedRewind> {
+        let rewind = Arc::new(Mutex::new(self.take_rewind()?));
+        let shared = rewind.clone();
+        self.rewind = Some(Box::new(move || (shared.lock().unwrap())()));
+        Some(rewind)
+    }
+
+    /// Returns a new body with the original data, which can be rewound through `rewind` again.
+    pub(crate) fn rewound(rewind: &SharedRewind) -> io::Result<Body> {
+        let body = (rewind.lock().unwrap())()?;
+        let rewind = rewind.clone();
+        Ok(body.with_rewind(move || (rewind.lock().unwrap())()))
+    }
+
     #[allow(dead_code)]
     pub(crate) fn try_clone(&self) -> Option<Body> {
-        self.kind.try_clone().map(|kind| Body { kind })
+        self.kind
+            .try_clone()
+            .map(|kind| Body { kind, rewind: None })
     }
 }
 
@@ -75,10 +131,29 @@ impl Kind {
     }
 }
 
+/// Reads the body, consuming it.
+/// This is how streamed response bodies (see [super::Client::send_streaming]) are read.
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.kind {
+            Kind::Reader(ref mut reader, _) => reader.read(buf),
+            Kind::Bytes(ref mut bytes) => Ok(read_buf(bytes, buf)),
+            Kind::BytesMut(ref mut bytes) => Ok(read_buf(bytes, buf)),
+        }
+    }
+}
+
+fn read_buf(src: &mut impl Buf, dst: &mut [u8]) -> usize {
+    let len = src.remaining().min(dst.len());
+    src.copy_to_slice(&mut dst[..len]);
+    len
+}
+
 impl Default for Body {
     fn default() -> Body {
         Body {
             kind: Kind::BytesMut(BytesMut::new()),
+            rewind: None,
         }
     }
 }
@@ -88,6 +163,7 @@ impl From<Vec<u8>> for Body {
     fn from(v: Vec<u8>) -> Body {
         Body {
             kind: Kind::Bytes(v.into()),
+            rewind: None,
         }
     }
 }
@@ -104,6 +180,7 @@ impl From<&'static [u8]> for Body {
     fn from(s: &'static [u8]) -> Body {
         Body {
             kind: Kind::Bytes(Bytes::from_static(s)),
+            rewind: None,
         }
     }
 }
@@ -117,10 +194,15 @@ impl From<&'static str> for Body {
 
 impl From<File> for Body {
     #[inline]
-    fn from(f: File) -> Body {
+    fn from(mut f: File) -> Body {
         let len = f.metadata().map(|m| m.len()).ok();
+        let rewind = match (f.stream_position(), f.try_clone()) {
+            (Ok(start), Ok(file)) => Some(rewind_file(file, start)),
+            _ => None,
+        };
         Body {
             kind: Kind::Reader(Box::new(f), len),
+            rewind,
         }
     }
 }
@@ -129,10 +211,29 @@ impl From<Bytes> for Body {
     fn from(b: Bytes) -> Body {
         Body {
             kind: Kind::Bytes(b),
+            rewind: None,
         }
     }
 }
 
+/// Rewinds the file by seeking back to the position it started at.
+fn rewind_file(file: File, start: u64) -> Box<RewindFn> {
+    Box::new(move || {
+        let mut file = file.try_clone()?;
+        file.seek(SeekFrom::Start(start))?;
+        Ok(Body::from(file))
+    })
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Body")
+            .field("kind", &self.kind)
+            .field("rewindable", &self.rewind.is_some())
+            .finish()
+    }
+}
+
 impl fmt::Debug for Kind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -156,3 +257,85 @@ impl<'a> fmt::Debug for DebugLength<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, File},
+        io::{Read, Write},
+    };
+
+    use super::Body;
+
+    #[test]
+    fn it_reads_buffered_body() {
+        let mut body = Body::from("hello world");
+        let mut buf = [0u8; 5];
+        assert_eq!(body.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf, b"hello");
+
+        let mut rest = String::new();
+        body.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, " world");
+        assert_eq!(body.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn it_reads_reader_body() {
+        let mut body = Body::new(&b"streamed"[..]);
+        let mut data = Vec::new();
+        body.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"streamed");
+    }
+
+    #[test]
+    fn it_rewinds_buffered_body() {
+        let mut body = Body::from("hello");
+        let mut rewind = body.take_rewind().unwrap();
+        let mut data = String::new();
+        body.read_to_string(&mut data).unwrap();
+
+        let mut rewound = rewind().unwrap();
+        let mut rewound_data = String::new();
+        rewound.read_to_string(&mut rewound_data).unwrap();
+        assert_eq!(rewound_data, "hello");
+    }
+
+    #[test]
+    fn it_rewinds_file_body() {
+        let path =
+            std::env::temp_dir().join(format!("cronet-rs-rewind-test-{}", std::process::id()));
+        File::create(&path).unwrap().write_all(b"file").unwrap();
+
+        let mut body = Body::from(File::open(&path).unwrap());
+        let mut rewind = body.take_rewind().unwrap();
+        let mut data = Vec::new();
+        body.read_to_end(&mut data).unwrap();
+
+        let mut rewound_data = Vec::new();
+        rewind().unwrap().read_to_end(&mut rewound_data).unwrap();
+        assert_eq!(rewound_data, b"file");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_shares_rewind() {
+        let mut body = Body::from("data");
+        let rewind = body.share_rewind().unwrap();
+
+        let mut rewound = Body::rewound(&rewind).unwrap();
+        let mut data = String::new();
+        rewound.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "data");
+        assert!(rewound.take_rewind().is_some());
+        assert!(body.take_rewind().is_some());
+    }
+
+    #[test]
+    fn it_only_rewinds_readers_with_rewind_fn() {
+        assert!(Body::new(&b"data"[..]).take_rewind().is_none());
+        let mut body = Body::new(&b"data"[..]).with_rewind(|| Ok(Body::from("data")));
+        assert!(body.take_rewind().is_some());
+    }
+}
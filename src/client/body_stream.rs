@@ -0,0 +1,138 @@
+use std::{
+    io::{self, Read},
+    sync::mpsc::Receiver,
+};
+
+use bytes::{Buf, Bytes};
+
+use super::RequestHandle;
+
+/// A part of a streamed response body, sent by the [super::ResponseHandler].
+#[derive(Debug)]
+pub(crate) enum Chunk {
+    Data(Bytes),
+    End,
+    Error(io::Error),
+    Canceled,
+}
+
+/// Response body that is read from the network on demand.
+///
+/// Every call to `read` that can't be served from the previously received chunk
+/// asks Cronet for the next chunk and blocks until it arrives,
+/// so the response is never read faster than it is consumed.
+///
+/// Dropping the stream before the end of the body cancels the request.
+pub struct BodyStream {
+    handle: RequestHandle,
+    rx: Receiver<Chunk>,
+    chunk: Bytes,
+    buffer_size: u64,
+    done: bool,
+}
+
+impl BodyStream {
+    pub(crate) fn new(handle: RequestHandle, rx: Receiver<Chunk>, buffer_size: u64) -> Self {
+        Self {
+            handle,
+            rx,
+            chunk: Bytes::new(),
+            buffer_size,
+            done: false,
+        }
+    }
+}
+
+impl Read for BodyStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while self.chunk.is_empty() {
+            if self.done {
+                return Ok(0);
+            }
+
+            // If the request already finished, its final chunk is on its way.
+            self.handle.read(self.buffer_size);
+
+            match self.rx.recv() {
+                Ok(Chunk::Data(data)) => self.chunk = data,
+                Ok(Chunk::End) => self.done = true,
+                Ok(Chunk::Error(error)) => {
+                    self.done = true;
+                    return Err(error);
+                }
+                Ok(Chunk::Canceled) | Err(_) => {
+                    self.done = true;
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "Request was cancelled",
+                    ));
+                }
+            }
+        }
+
+        let len = self.chunk.len().min(buf.len());
+        self.chunk.copy_to_slice(&mut buf[..len]);
+        Ok(len)
+    }
+}
+
+impl Drop for BodyStream {
+    fn drop(&mut self) {
+        if !self.done {
+            self.handle.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Read},
+        sync::mpsc,
+    };
+
+    use bytes::Bytes;
+
+    use crate::{client::RequestHandle, UrlRequest};
+
+    use super::{BodyStream, Chunk};
+
+    fn finished_handle() -> RequestHandle {
+        let handle = RequestHandle::new(UrlRequest::new());
+        handle.release();
+        handle
+    }
+
+    #[test]
+    fn it_reads_chunks_until_the_end() {
+        let (tx, rx) = mpsc::channel();
+        tx.send(Chunk::Data(Bytes::from_static(b"hello "))).unwrap();
+        tx.send(Chunk::Data(Bytes::from_static(b"world"))).unwrap();
+        tx.send(Chunk::End).unwrap();
+
+        let mut stream = BodyStream::new(finished_handle(), rx, 512);
+        let mut body = String::new();
+        stream.read_to_string(&mut body).unwrap();
+        assert_eq!(body, "hello world");
+        assert_eq!(stream.read(&mut [0u8; 4]).unwrap(), 0);
+    }
+
+    #[test]
+    fn it_reports_errors() {
+        let (tx, rx) = mpsc::channel();
+        tx.send(Chunk::Data(Bytes::from_static(b"partial")))
+            .unwrap();
+        tx.send(Chunk::Error(io::Error::other("connection reset")))
+            .unwrap();
+
+        let mut stream = BodyStream::new(finished_handle(), rx, 512);
+        let mut body = Vec::new();
+        let error = stream.read_to_end(&mut body).unwrap_err();
+        assert_eq!(error.to_string(), "connection reset");
+        assert_eq!(body, b"partial");
+    }
+}
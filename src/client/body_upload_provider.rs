@@ -1,10 +1,10 @@
-use crate::{Buffer, Destroy, UploadDataProvider, UploadDataProviderHandler, UploadDataSink};
+use std::io::{self, Read};
 
-use super::Body;
+use crate::{Borrowed, Buffer, UploadDataProvider, UploadDataProviderHandler, UploadDataSink};
 
-type RewindFn = dyn Fn() -> Body;
+use super::{Body, RewindFn};
 
-pub struct BodyUploadDataProvider<'a> {
+pub struct BodyUploadDataProvider {
     /// The HTTP request body to be uploaded.
     body: Body,
 
@@ -12,98 +12,212 @@ pub struct BodyUploadDataProvider<'a> {
     /// This is used for retrying requests when the server times out stale sockets.
     /// Note that rewinding is also required to follow redirects that preserve the upload body.
     /// If this is `None`, rewinding is not supported.
-    rewind: Option<&'a RewindFn>,
-}
+    rewind: Option<Box<RewindFn>>,
 
-impl<'a> UploadDataProviderHandler for BodyUploadDataProvider<'a> {
-    fn length(&self, _: UploadDataProvider) -> i64 {
-        self.body.len().unwrap_or(0) as i64
-    }
+    /// The length of the body when the upload started, or `None` for chunked uploads.
+    length: Option<u64>,
 
-    fn read(&self, _: UploadDataProvider, sink: UploadDataSink, buffer: Buffer) {
-        if let Some(bytes) = self.body.as_bytes() {
-            let len = bytes.len() as u64;
+    /// The number of bytes uploaded since the upload started.
+    uploaded: u64,
+}
 
-            if len == 0 {
-                sink.on_read_error("Empty body");
-                return;
-            }
+impl UploadDataProviderHandler for BodyUploadDataProvider {
+    fn length(&self, _: Borrowed<'_, UploadDataProvider>) -> i64 {
+        // Bodies of unknown length are uploaded using chunked transfer encoding.
+        self.length.map_or(-1, |len| len as i64)
+    }
 
-            match buffer.write_slice(bytes, len) {
-                Ok(_) => {
-                    sink.on_read_succeeded(len, false); // TODO: implement chunked reads
+    fn read(
+        &mut self,
+        _: Borrowed<'_, UploadDataProvider>,
+        sink: Borrowed<'_, UploadDataSink>,
+        mut buffer: Borrowed<'_, Buffer>,
+    ) {
+        // Bodies of known length are never read beyond their length.
+        let remaining = self.length.map_or(u64::MAX, |len| len - self.uploaded);
+        let size = buffer.size().min(remaining) as usize;
+        let data = buffer.data_slice_mut::<u8>(size);
+
+        // Fill the buffer as much as possible, the remaining data is read in the next call.
+        let mut bytes_read = 0;
+        let mut is_eof = false;
+        while bytes_read < size {
+            match self.body.read(&mut data[bytes_read..]) {
+                Ok(0) => {
+                    is_eof = true;
+                    break;
                 }
+                Ok(n) => bytes_read += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
                 Err(err) => {
-                    sink.on_read_error(err);
+                    sink.on_read_error(&err.to_string());
+                    return;
                 }
             }
-        } else {
-            sink.on_read_error("Invalid body");
+        }
+
+        self.uploaded += bytes_read as u64;
+        match self.length {
+            Some(len) if is_eof && self.uploaded < len => {
+                sink.on_read_error(&format!(
+                    "Body ended after {} of {} bytes",
+                    self.uploaded, len
+                ));
+            }
+            Some(_) => sink.on_read_succeeded(bytes_read as u64, false),
+            None => sink.on_read_succeeded(bytes_read as u64, is_eof),
         }
     }
 
-    fn rewind(&mut self, _: UploadDataProvider, sink: UploadDataSink) {
-        if let Some(rewind) = &self.rewind {
-            self.body = rewind();
-            sink.on_rewind_succeeded();
+    fn rewind(&mut self, _: Borrowed<'_, UploadDataProvider>, sink: Borrowed<'_, UploadDataSink>) {
+        if let Some(rewind) = &mut self.rewind {
+            match rewind() {
+                Ok(body) => {
+                    self.length = body.len();
+                    self.uploaded = 0;
+                    self.body = body;
+                    sink.on_rewind_succeeded();
+                }
+                Err(err) => sink.on_rewind_error(&err.to_string()),
+            }
         } else {
             sink.on_rewind_error("Rewinding is not supported");
         }
     }
 
-    fn close(&self, upload_data_provider: UploadDataProvider) {
-        upload_data_provider.destroy();
+    fn close(&self, _: Borrowed<'_, UploadDataProvider>) {}
+}
+
+impl BodyUploadDataProvider {
+    pub fn new(body: Body, rewind: Option<Box<RewindFn>>) -> Self {
+        Self {
+            length: body.len(),
+            uploaded: 0,
+            body,
+            rewind,
+        }
     }
 }
 
-impl<'a> BodyUploadDataProvider<'a> {
-    pub fn new(body: Body, rewind: Option<&'a RewindFn>) -> Self {
-        Self { body, rewind }
+impl From<Body> for BodyUploadDataProvider {
+    /// Creates a provider that rewinds the body if it is rewindable (see [Body::with_rewind]).
+    fn from(mut body: Body) -> Self {
+        let rewind = body.take_rewind();
+        Self::new(body, rewind)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use bytes::Bytes;
+    use std::{
+        io::Cursor,
+        sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    };
 
     use crate::{
-        client::Body, Buffer, Destroy, UploadDataProvider, UploadDataProviderHandler,
+        client::Body, Borrowed, Buffer, UploadDataProvider, UploadDataProviderHandler,
         UploadDataSink, UploadDataSinkCallbacks,
     };
 
     use super::BodyUploadDataProvider;
 
+    fn provider_dummy() -> UploadDataProvider {
+        UploadDataProvider::new(BodyUploadDataProvider::new(Body::from(""), None))
+    }
+
+    /// Lends `provider` to a handler, like Cronet does.
+    fn lend(provider: &UploadDataProvider) -> Borrowed<'_, UploadDataProvider> {
+        unsafe { Borrowed::new(UploadDataProvider { ptr: provider.ptr }) }
+    }
+
+    fn read(
+        handler: &mut BodyUploadDataProvider,
+        callbacks: UploadDataSinkCallbacks,
+        size: u64,
+    ) -> Vec<u8> {
+        let provider = provider_dummy();
+        let sink = UploadDataSink::new(callbacks);
+        let buffer = Buffer::new_with_size(size);
+
+        unsafe {
+            handler.read(
+                lend(&provider),
+                Borrowed::new(UploadDataSink { ptr: sink.ptr }),
+                Borrowed::new(Buffer { ptr: buffer.ptr }),
+            );
+        }
+
+        buffer.data_slice::<u8>(size as usize).to_vec()
+    }
+
     #[test]
     fn test_body_upload_data_provider() {
+        static BYTES_READ: AtomicU64 = AtomicU64::new(0);
+        let callbacks = UploadDataSinkCallbacks {
+            on_read_succeeded: |_, bytes_read, _| BYTES_READ.store(bytes_read, Ordering::SeqCst),
+            on_read_error: |_, _| {},
+            on_rewind_succeeded: |_| {},
+            on_rewind_error: |_, _| {},
+        };
+
         let expected = "test";
+        let mut handler = BodyUploadDataProvider::new(Body::from(expected), None);
+        assert_eq!(handler.length(lend(&provider_dummy())), 4);
 
-        let handler = BodyUploadDataProvider::new(Body::from(expected), None);
-        let provider_dummy =
-            UploadDataProvider::new(BodyUploadDataProvider::new(Body::from(""), None));
-        let sink = UploadDataSink::new(UploadDataSinkCallbacks {
-            on_read_succeeded: |_, _, _| {},
+        let actual = read(&mut handler, callbacks, 4);
+        assert_eq!(BYTES_READ.load(Ordering::SeqCst), 4);
+        assert_eq!(actual, expected.as_bytes());
+    }
+
+    #[test]
+    fn it_fills_buffers_across_reads() {
+        static BYTES_READ: AtomicU64 = AtomicU64::new(0);
+        static IS_FINAL_CHUNK: AtomicBool = AtomicBool::new(false);
+        let callbacks = UploadDataSinkCallbacks {
+            on_read_succeeded: |_, bytes_read, is_final_chunk| {
+                BYTES_READ.store(bytes_read, Ordering::SeqCst);
+                IS_FINAL_CHUNK.store(is_final_chunk, Ordering::SeqCst);
+            },
             on_read_error: |_, _| {},
             on_rewind_succeeded: |_| {},
             on_rewind_error: |_, _| {},
-        });
+        };
 
-        let buffer = Buffer::new();
-        buffer.init_data_and_callback(
-            Box::new(Bytes::new()),
-            expected.len() as u64,
-            crate::BufferCallback::new(|_, _| {}),
-        );
+        let mut handler =
+            BodyUploadDataProvider::new(Body::new(Cursor::new(b"hello world".to_vec())), None);
+        assert_eq!(handler.length(lend(&provider_dummy())), -1);
 
-        let ptr = buffer.ptr;
+        let first = read(&mut handler, callbacks, 8);
+        assert_eq!(BYTES_READ.load(Ordering::SeqCst), 8);
+        assert!(!IS_FINAL_CHUNK.load(Ordering::SeqCst));
+        assert_eq!(first, b"hello wo");
 
-        handler.read(provider_dummy, sink, buffer);
+        let second = read(&mut handler, callbacks, 8);
+        assert_eq!(BYTES_READ.load(Ordering::SeqCst), 3);
+        assert!(IS_FINAL_CHUNK.load(Ordering::SeqCst));
+        assert_eq!(&second[..3], b"rld");
+    }
 
-        // Read the modified buffer again by its pointer.
-        let buffer = Buffer { ptr };
-        let actual = buffer.data_slice::<u8>(4);
-        assert_eq!(actual.len(), expected.len());
-        assert_eq!(actual, expected.as_bytes());
+    #[test]
+    fn it_fails_bodies_shorter_than_their_length() {
+        static BYTES_READ: AtomicU64 = AtomicU64::new(0);
+        static FAILED: AtomicBool = AtomicBool::new(false);
+        let callbacks = UploadDataSinkCallbacks {
+            on_read_succeeded: |_, bytes_read, _| BYTES_READ.store(bytes_read, Ordering::SeqCst),
+            on_read_error: |_, _| FAILED.store(true, Ordering::SeqCst),
+            on_rewind_succeeded: |_| {},
+            on_rewind_error: |_, _| {},
+        };
+
+        let body = Body::sized(Cursor::new(b"short".to_vec()), 10);
+        let mut handler = BodyUploadDataProvider::new(body, None);
+        assert_eq!(handler.length(lend(&provider_dummy())), 10);
+
+        read(&mut handler, callbacks, 4);
+        assert_eq!(BYTES_READ.load(Ordering::SeqCst), 4);
+        assert!(!FAILED.load(Ordering::SeqCst));
 
-        buffer.destroy();
+        read(&mut handler, callbacks, 8);
+        assert!(FAILED.load(Ordering::SeqCst));
     }
 }
@@ -1,87 +1,242 @@
-use std::{sync::mpsc, thread};
+use std::{
+    mem::ManuallyDrop,
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
 
 use crate::{
-    client::ClientError, Destroy, Engine, EngineParams, EngineResult, Executor, UrlRequest,
-    UrlRequestCallback, UrlRequestParams,
+    client::ClientError, executor, Engine, EngineResult, Executor, UrlRequest, UrlRequestCallback,
+    UrlRequestParams,
 };
 
-use super::{Body, ResponseHandler, ShouldRedirectFn, Status};
+use http::HeaderName;
 
-pub struct Client {
-    pub should_redirect: ShouldRedirectFn,
-    pub engine: Engine,
-    pub executor: Executor,
+#[cfg(feature = "cookies")]
+use super::CookieJar;
+use super::{
+    retry, timeout, Body, ClientBuilder, ExecutorKind, PendingRequest, ReadTimeout, RedirectPolicy,
+    RequestHandle, RequestTemplate, ResponseHandler, RetryPolicy, Status, StatusSink, Timeout,
+};
+
+/// The engine and executor of a [Client], shared by its clones and by the requests that may need them later.
+pub(crate) struct ClientCore {
+    pub(crate) engine: ManuallyDrop<Engine>,
+    pub(crate) executor: ManuallyDrop<Executor>,
 }
 
-impl Destroy for Client {
-    fn destroy(&self) {
-        self.engine.shutdown();
-        self.engine.destroy();
-        self.executor.destroy();
+// Cronet allows engines and executors to be used from any thread.
+unsafe impl Sync for ClientCore {}
+
+impl Drop for ClientCore {
+    /// Shuts down and destroys the engine and executor.
+    ///
+    /// On a network thread of Cronet (e.g. in a callback of [ExecutorKind::Direct]) the engine cannot be shut down,
+    /// so this is done on another thread instead.
+    fn drop(&mut self) {
+        let (engine, executor) = unsafe {
+            (
+                ManuallyDrop::take(&mut self.engine),
+                ManuallyDrop::take(&mut self.executor),
+            )
+        };
+        let teardown = move || {
+            engine.shutdown();
+            drop(engine);
+            drop(executor);
+        };
+        if executor::on_network_thread() {
+            thread::spawn(teardown);
+        } else {
+            teardown();
+        }
     }
 }
 
-#[allow(dead_code)]
+/// A client that sends [http::Request]s through Cronet.
+///
+/// Clones share the same engine, which is shut down once the last clone is dropped.
+#[derive(Clone)]
+pub struct Client {
+    pub redirect_policy: Arc<RedirectPolicy>,
+    pub retry_policy: Arc<RetryPolicy>,
+    pub(crate) core: Arc<ClientCore>,
+    /// The built-in executor, or `None` if the executor was provided by the app.
+    pub(crate) executor_kind: Option<ExecutorKind>,
+    /// Buffer size for reading response bodies.
+    pub read_buffer_size: u64,
+    /// Default total timeout of requests (see [Timeout]).
+    pub timeout: Option<Duration>,
+    /// Default read timeout of requests (see [ReadTimeout]).
+    pub read_timeout: Option<Duration>,
+    /// Headers that are removed when a request is redirected to another origin.
+    pub sensitive_headers: Arc<Vec<HeaderName>>,
+    /// Jar that stores the cookies of responses and sends them with matching requests.
+    #[cfg(feature = "cookies")]
+    pub cookie_jar: Option<Arc<CookieJar>>,
+}
+
 impl Client {
+    /// Creates a client with the default configuration (see [ClientBuilder]).
+    ///
+    /// Panics if the engine fails to start.
     pub fn new() -> Self {
-        let engine_params = EngineParams::new();
-        engine_params.set_enable_http_2(true);
-        engine_params.set_enable_quic(true);
-        engine_params.set_enable_brotli(true);
-        engine_params.set_user_agent("cronet");
-
-        let engine = Engine::new();
-        engine.start(engine_params);
-
-        let executor = Executor::new(|_, runnable| {
-            thread::spawn(move || {
-                runnable.run();
-                runnable.destroy();
-            });
-        });
-
-        Self {
-            engine,
-            executor,
-            should_redirect: |_| true,
-        }
+        ClientBuilder::new()
+            .build()
+            .expect("Failed to start the cronet engine")
+    }
+
+    /// Returns a [ClientBuilder] to configure a new client.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// The engine that sends the requests of this client.
+    pub fn engine(&self) -> &Engine {
+        &self.core.engine
     }
 
-    /// Sets the function that determines whether a redirect should be followed.
-    pub fn set_should_redirect(&mut self, should_redirect: ShouldRedirectFn) {
-        self.should_redirect = should_redirect;
+    /// The executor that runs the callbacks of this client.
+    pub fn executor(&self) -> &Executor {
+        &self.core.executor
     }
 
+    /// Sets the policy that determines whether redirects are followed.
+    pub fn set_redirect_policy(&mut self, redirect_policy: RedirectPolicy) {
+        self.redirect_policy = Arc::new(redirect_policy);
+    }
+
+    /// Sets the policy that determines whether failed requests are retried.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = Arc::new(retry_policy);
+    }
+
+    /// Sends the request and blocks until its response body is received.
+    /// Returns [ClientError::CancellationError] if the request was cancelled, or ended without a status.
     pub fn send(&self, request: http::Request<Body>) -> Result<http::Response<Body>, ClientError> {
+        let (tx, rx) = mpsc::channel::<Status>();
+        let handle = self.start(request, tx, false)?;
+        PendingRequest { rx, handle }.wait()
+    }
+
+    /// Starts the request and returns without waiting for its response.
+    ///
+    /// The returned [PendingRequest] provides a [super::CancelToken] to cancel the request from another thread,
+    /// in which case [PendingRequest::wait] returns [ClientError::CancellationError].
+    pub fn send_cancellable(
+        &self,
+        request: http::Request<Body>,
+    ) -> Result<PendingRequest, ClientError> {
+        let (tx, rx) = mpsc::channel::<Status>();
+        let handle = self.start(request, tx, false)?;
+        Ok(PendingRequest { rx, handle })
+    }
+
+    /// Sends the request and returns as soon as the response headers are received.
+    ///
+    /// The body of the returned response is read from the network on demand through [std::io::Read],
+    /// so large responses can be processed incrementally without buffering them in memory.
+    /// Dropping the body before it has been read completely cancels the request.
+    pub fn send_streaming(
+        &self,
+        request: http::Request<Body>,
+    ) -> Result<http::Response<Body>, ClientError> {
+        let (tx, rx) = mpsc::channel::<Status>();
+        let handle = self.start(request, tx, true)?;
+        PendingRequest { rx, handle }.wait()
+    }
+
+    /// Starts the request and returns a handle to it.
+    /// The final [Status] of the request is reported to the given `sink`.
+    /// If `streaming` is set, the status is reported as soon as the response headers are received.
+    pub(crate) fn start(
+        &self,
+        request: http::Request<Body>,
+        sink: impl StatusSink + 'static,
+        streaming: bool,
+    ) -> Result<RequestHandle, ClientError> {
+        let handle = RequestHandle::new(UrlRequest::new()).with_core(self.core.clone());
+        let (request, sink) = retry::wrap(self, &handle, request, sink, streaming);
+        self.run(&handle, request, sink, streaming)?;
+        Ok(handle)
+    }
+
+    /// Sends the request through the new [UrlRequest] of `handle`.
+    pub(crate) fn run(
+        &self,
+        handle: &RequestHandle,
+        request: http::Request<Body>,
+        sink: impl StatusSink + 'static,
+        streaming: bool,
+    ) -> Result<(), ClientError> {
         let uri = request.uri().to_string();
+        let extensions = request.extensions();
+        let timeout = extensions.get::<Timeout>().map(|t| t.0).or(self.timeout);
+        let read_timeout = extensions
+            .get::<ReadTimeout>()
+            .map(|t| t.0)
+            .or(self.read_timeout);
 
+        let (mut template, request) =
+            RequestTemplate::new(self.core.clone(), request, self.sensitive_headers.clone());
+        template.allow_direct_executor = self.allow_direct_executor();
+        #[cfg(feature = "cookies")]
+        let (template, request) = self.add_cookies(template, request);
         let request_parameters = UrlRequestParams::from(request);
-        request_parameters.set_upload_data_executor(&self.executor);
+        request_parameters.set_upload_data_executor(self.executor());
+        request_parameters.set_allow_direct_executor(template.allow_direct_executor);
 
-        let (tx, rx) = mpsc::channel::<Status>();
-        let response_handler = ResponseHandler::new(self.should_redirect, tx);
+        let mut response_handler = ResponseHandler::new(self.redirect_policy.clone(), sink);
+        response_handler.set_request_handle(handle.clone());
+        response_handler.set_buffer_size(self.read_buffer_size);
+        response_handler.set_streaming(streaming);
+        #[cfg(feature = "cookies")]
+        if let Some(jar) = &self.cookie_jar {
+            response_handler.set_cookie_jar(jar.clone());
+        }
+        if template.is_active() {
+            response_handler.set_request_template(template);
+        }
         let callback = UrlRequestCallback::new(response_handler);
-        let url_request = UrlRequest::new();
-        url_request.init_with_params(
-            &self.engine,
+        let mut result = handle.init(
+            self.engine(),
             uri.as_str(),
             &request_parameters,
-            &callback,
-            &self.executor,
+            callback,
+            self.executor(),
         );
-        // request_parameters.destroy();
-        let result = url_request.start();
+        if result == EngineResult::Success {
+            result = handle.start();
+        }
         if result != EngineResult::Success {
-            return Result::Err(ClientError::EngineError(result));
+            handle.release();
+            return Err(ClientError::EngineError(result));
         }
 
-        let status = rx.recv().unwrap();
+        timeout::watch(handle.clone(), timeout, read_timeout);
 
-        match status {
-            Status::Success(res) => Result::Ok(res),
-            Status::Canceled => Result::Err(ClientError::CancellationError),
-            Status::Error(e) => Result::Err(ClientError::CronetError(e)),
+        Ok(())
+    }
+
+    /// Whether Cronet may run callbacks directly on the network thread (see [ExecutorKind::Direct]).
+    fn allow_direct_executor(&self) -> bool {
+        self.executor_kind.is_some_and(ExecutorKind::is_direct)
+    }
+
+    /// Adds the matching cookies of the cookie jar to the request,
+    /// unless the request already has a `Cookie` header.
+    #[cfg(feature = "cookies")]
+    fn add_cookies(
+        &self,
+        mut template: RequestTemplate,
+        mut request: http::Request<Body>,
+    ) -> (RequestTemplate, http::Request<Body>) {
+        if let Some(jar) = &self.cookie_jar {
+            if !request.headers().contains_key(http::header::COOKIE) {
+                template.set_cookie_jar(jar.clone(), &mut request);
+            }
         }
+        (template, request)
     }
 }
 
@@ -90,3 +245,35 @@ impl Default for Client {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    use super::Client;
+    use crate::client::Body;
+
+    #[test]
+    fn it_keeps_the_engine_of_pending_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let client = Client::builder().sensitive_headers([]).build().unwrap();
+        let request = http::Request::get(url).body(Body::default()).unwrap();
+        let pending = client.send_cancellable(request).unwrap();
+        drop(client);
+
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request).unwrap();
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+            .unwrap();
+
+        let response = pending.wait().unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(response.body().as_bytes(), Some(&b"ok"[..]));
+    }
+}
@@ -0,0 +1,420 @@
+use std::{
+    mem::ManuallyDrop,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    client::ClientError, Engine, EngineParams, EngineResult, Executor, HttpCacheMode,
+    PublicKeyPins, QuicHint,
+};
+
+use http::HeaderName;
+
+#[cfg(feature = "cookies")]
+use super::CookieJar;
+use super::{
+    Client, ClientCore, ExecutorKind, RedirectPolicy, RetryPolicy, DEFAULT_SENSITIVE_HEADERS,
+};
+
+struct QuicHintConfig {
+    host: String,
+    port: i32,
+    alternate_port: i32,
+}
+
+struct PublicKeyPinsConfig {
+    host: String,
+    pins_sha256: Vec<String>,
+    include_subdomains: bool,
+    expiration_date: SystemTime,
+}
+
+/// Builder to configure the engine and settings of a [Client].
+///
+/// ```no_run
+/// use cronet_rs::client::ClientBuilder;
+///
+/// let client = ClientBuilder::new()
+///     .user_agent("my-app/1.0")
+///     .enable_quic(false)
+///     .build()
+///     .unwrap();
+/// ```
+pub struct ClientBuilder {
+    enable_check_result: Option<bool>,
+    user_agent: Option<String>,
+    accept_language: Option<String>,
+    storage_path: Option<String>,
+    enable_quic: bool,
+    enable_http_2: bool,
+    enable_brotli: bool,
+    http_cache_mode: Option<HttpCacheMode>,
+    http_cache_max_size: Option<i64>,
+    quic_hints: Vec<QuicHintConfig>,
+    public_key_pins: Vec<PublicKeyPinsConfig>,
+    enable_public_key_pinning_bypass_for_local_trust_anchors: Option<bool>,
+    network_thread_priority: Option<f64>,
+    experimental_options: Option<String>,
+    redirect_policy: RedirectPolicy,
+    retry_policy: RetryPolicy,
+    executor: Option<Executor>,
+    executor_kind: ExecutorKind,
+    read_buffer_size: u64,
+    timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    sensitive_headers: Vec<HeaderName>,
+    #[cfg(feature = "cookies")]
+    cookie_jar: Option<Arc<CookieJar>>,
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        Self {
+            enable_check_result: None,
+            user_agent: Some(String::from("cronet")),
+            accept_language: None,
+            storage_path: None,
+            enable_quic: true,
+            enable_http_2: true,
+            enable_brotli: true,
+            http_cache_mode: None,
+            http_cache_max_size: None,
+            quic_hints: Vec::new(),
+            public_key_pins: Vec::new(),
+            enable_public_key_pinning_bypass_for_local_trust_anchors: None,
+            network_thread_priority: None,
+            experimental_options: None,
+            redirect_policy: RedirectPolicy::default(),
+            retry_policy: RetryPolicy::default(),
+            executor: None,
+            executor_kind: ExecutorKind::default(),
+            read_buffer_size: 512,
+            timeout: None,
+            read_timeout: None,
+            sensitive_headers: DEFAULT_SENSITIVE_HEADERS.to_vec(),
+            #[cfg(feature = "cookies")]
+            cookie_jar: None,
+        }
+    }
+
+    /// Enables or disables checking the results of Cronet API calls.
+    pub fn enable_check_result(mut self, enable: bool) -> Self {
+        self.enable_check_result = Some(enable);
+        self
+    }
+
+    /// Sets the `User-Agent` header for all requests.
+    /// The default is `cronet`.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Sets the `Accept-Language` header for all requests.
+    pub fn accept_language(mut self, accept_language: &str) -> Self {
+        self.accept_language = Some(accept_language.to_string());
+        self
+    }
+
+    /// Sets the directory for HTTP cache and prefs storage.
+    /// The directory must exist.
+    pub fn storage_path(mut self, storage_path: &str) -> Self {
+        self.storage_path = Some(storage_path.to_string());
+        self
+    }
+
+    /// Enables or disables QUIC. Enabled by default.
+    pub fn enable_quic(mut self, enable: bool) -> Self {
+        self.enable_quic = enable;
+        self
+    }
+
+    /// Enables or disables HTTP/2. Enabled by default.
+    pub fn enable_http_2(mut self, enable: bool) -> Self {
+        self.enable_http_2 = enable;
+        self
+    }
+
+    /// Enables or disables Brotli compression. Enabled by default.
+    pub fn enable_brotli(mut self, enable: bool) -> Self {
+        self.enable_brotli = enable;
+        self
+    }
+
+    /// Sets the HTTP cache mode.
+    /// The disk modes require a storage path (see `storage_path`).
+    pub fn http_cache_mode(mut self, mode: HttpCacheMode) -> Self {
+        self.http_cache_mode = Some(mode);
+        self
+    }
+
+    /// Sets the maximum size of the HTTP cache in bytes.
+    pub fn http_cache_max_size(mut self, max_size: i64) -> Self {
+        self.http_cache_max_size = Some(max_size);
+        self
+    }
+
+    /// Adds a hint that `host` supports QUIC on `port`, using `alternate_port` for QUIC.
+    pub fn quic_hint(mut self, host: &str, port: i32, alternate_port: i32) -> Self {
+        self.quic_hints.push(QuicHintConfig {
+            host: host.to_string(),
+            port,
+            alternate_port,
+        });
+        self
+    }
+
+    /// Pins the public keys of `host` until `expiration_date`.
+    ///
+    /// Arguments:
+    ///
+    /// * `pins_sha256`: SHA-256 hashes of the public keys, in the form `sha256/<base64-hash>`.
+    /// * `include_subdomains`: Whether the pins also apply to subdomains of `host`.
+    pub fn public_key_pins(
+        mut self,
+        host: &str,
+        pins_sha256: &[&str],
+        include_subdomains: bool,
+        expiration_date: SystemTime,
+    ) -> Self {
+        self.public_key_pins.push(PublicKeyPinsConfig {
+            host: host.to_string(),
+            pins_sha256: pins_sha256.iter().map(|pin| pin.to_string()).collect(),
+            include_subdomains,
+            expiration_date,
+        });
+        self
+    }
+
+    /// Enables or disables the public key pinning bypass for local trust anchors.
+    pub fn enable_public_key_pinning_bypass_for_local_trust_anchors(
+        mut self,
+        enable: bool,
+    ) -> Self {
+        self.enable_public_key_pinning_bypass_for_local_trust_anchors = Some(enable);
+        self
+    }
+
+    /// Sets the priority of the network thread.
+    /// It's recommended to leave this unspecified on platforms other than Android and iOS.
+    pub fn network_thread_priority(mut self, priority: f64) -> Self {
+        self.network_thread_priority = Some(priority);
+        self
+    }
+
+    /// Sets a JSON formatted string of experimental engine options.
+    pub fn experimental_options(mut self, options: &str) -> Self {
+        self.experimental_options = Some(options.to_string());
+        self
+    }
+
+    /// Sets the policy that determines whether redirects are followed.
+    /// By default, up to 10 redirects are followed.
+    pub fn redirect_policy(mut self, redirect_policy: RedirectPolicy) -> Self {
+        self.redirect_policy = redirect_policy;
+        self
+    }
+
+    /// Sets the policy that determines whether failed requests are retried.
+    /// By default, requests aren't retried.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the executor that runs the callbacks of requests, instead of a built-in one.
+    /// With the `tokio` feature, [Executor::new_with_tokio] runs them on an existing Tokio runtime.
+    pub fn executor(mut self, executor: Executor) -> Self {
+        self.executor = Some(executor);
+        self
+    }
+
+    /// Sets the built-in executor that runs the callbacks of requests (see [ExecutorKind]).
+    /// Ignored if a custom executor is set.
+    pub fn executor_kind(mut self, kind: ExecutorKind) -> Self {
+        self.executor_kind = kind;
+        self
+    }
+
+    /// Sets the buffer size for reading response bodies.
+    /// The default is `512` bytes. A size of `0` makes `build` fail with [EngineResult::IllegalArgument].
+    pub fn read_buffer_size(mut self, buffer_size: u64) -> Self {
+        self.read_buffer_size = buffer_size;
+        self
+    }
+
+    /// Sets the total timeout of requests, from starting the request until the end of the response body.
+    /// Can be overridden per request using the [super::Timeout] extension.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long requests may wait on the network without making progress.
+    /// Can be overridden per request using the [super::ReadTimeout] extension.
+    pub fn read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = Some(read_timeout);
+        self
+    }
+
+    /// Sets the headers that are removed when a request is redirected to another origin.
+    /// Such requests are sent again to the new location without these headers.
+    /// The default is [DEFAULT_SENSITIVE_HEADERS]; an empty set disables this.
+    pub fn sensitive_headers(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+        self.sensitive_headers = headers.into_iter().collect();
+        self
+    }
+
+    /// Sets the jar that stores the cookies of responses and sends them with matching requests.
+    /// Requests that already have a `Cookie` header are sent as is.
+    #[cfg(feature = "cookies")]
+    pub fn cookie_jar(mut self, jar: Arc<CookieJar>) -> Self {
+        self.cookie_jar = Some(jar);
+        self
+    }
+
+    /// Starts the engine and returns the configured [Client].
+    pub fn build(self) -> Result<Client, ClientError> {
+        if self.read_buffer_size == 0 {
+            return Err(ClientError::EngineError(EngineResult::IllegalArgument));
+        }
+
+        let engine = Engine::new();
+        let result = engine.start(self.engine_params());
+        if result != EngineResult::Success {
+            return Err(ClientError::EngineError(result));
+        }
+
+        // The worker threads of a built-in executor are only spawned once the engine is running.
+        let (executor, executor_kind) = match self.executor {
+            Some(executor) => (executor, None),
+            None => (self.executor_kind.create(), Some(self.executor_kind)),
+        };
+
+        Ok(Client {
+            core: Arc::new(ClientCore {
+                engine: ManuallyDrop::new(engine),
+                executor: ManuallyDrop::new(executor),
+            }),
+            executor_kind,
+            redirect_policy: Arc::new(self.redirect_policy),
+            retry_policy: Arc::new(self.retry_policy),
+            read_buffer_size: self.read_buffer_size,
+            timeout: self.timeout,
+            read_timeout: self.read_timeout,
+            sensitive_headers: Arc::new(self.sensitive_headers),
+            #[cfg(feature = "cookies")]
+            cookie_jar: self.cookie_jar,
+        })
+    }
+
+    fn engine_params(&self) -> EngineParams {
+        let engine_params = EngineParams::new();
+        if let Some(enable) = self.enable_check_result {
+            engine_params.set_enable_check_result(enable);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            engine_params.set_user_agent(user_agent);
+        }
+        if let Some(accept_language) = &self.accept_language {
+            engine_params.set_accept_language(accept_language);
+        }
+        if let Some(storage_path) = &self.storage_path {
+            engine_params.set_storage_path(storage_path);
+        }
+        engine_params.set_enable_quic(self.enable_quic);
+        engine_params.set_enable_http_2(self.enable_http_2);
+        engine_params.set_enable_brotli(self.enable_brotli);
+        if let Some(mode) = self.http_cache_mode {
+            engine_params.set_http_cache_mode(mode);
+        }
+        if let Some(max_size) = self.http_cache_max_size {
+            engine_params.set_http_cache_max_size(max_size);
+        }
+
+        // The engine params keep a copy of each hint and pin.
+        for hint in &self.quic_hints {
+            let quic_hint = QuicHint::new();
+            quic_hint.set_host(&hint.host);
+            quic_hint.set_port(hint.port);
+            quic_hint.set_alternate_port(hint.alternate_port);
+            engine_params.add_quic_hint(&quic_hint);
+        }
+        for pins in &self.public_key_pins {
+            let public_key_pins = PublicKeyPins::new();
+            public_key_pins.set_host(&pins.host);
+            for pin in &pins.pins_sha256 {
+                public_key_pins.add(pin);
+            }
+            public_key_pins.set_include_subdomains(pins.include_subdomains);
+            public_key_pins.set_expiration_date(pins.expiration_date);
+            engine_params.add_public_key_pins(&public_key_pins);
+        }
+
+        if let Some(enable) = self.enable_public_key_pinning_bypass_for_local_trust_anchors {
+            engine_params.set_enable_public_key_pinning_bypass_for_local_trust_anchors(enable);
+        }
+        if let Some(priority) = self.network_thread_priority {
+            engine_params.set_network_thread_priority(priority);
+        }
+        if let Some(options) = &self.experimental_options {
+            engine_params.set_experimental_options(options);
+        }
+        engine_params
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{client::ClientError, EngineResult, HttpCacheMode};
+
+    use super::ClientBuilder;
+
+    #[test]
+    fn it_builds_engine_params() {
+        let builder = ClientBuilder::new()
+            .user_agent("test")
+            .accept_language("en-US")
+            .enable_quic(false)
+            .http_cache_mode(HttpCacheMode::InMemory)
+            .http_cache_max_size(1024)
+            .quic_hint("example.com", 443, 443)
+            .public_key_pins(
+                "example.com",
+                &["sha256/AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="],
+                true,
+                UNIX_EPOCH + Duration::from_secs(86400),
+            )
+            .experimental_options("{}");
+
+        let engine_params = builder.engine_params();
+        assert_eq!(engine_params.user_agent(), "test");
+        assert_eq!(engine_params.accept_language(), "en-US");
+        assert!(!engine_params.enable_quic());
+        assert!(engine_params.enable_http_2());
+        assert_eq!(engine_params.http_cache_max_size(), 1024);
+        assert_eq!(engine_params.quic_hints_size(), 1);
+        assert_eq!(engine_params.quic_hint_at(0).host(), "example.com");
+        assert_eq!(engine_params.public_key_pins_size(), 1);
+        assert_eq!(engine_params.public_key_pins_at(0).size(), 1);
+        assert_eq!(engine_params.experimental_options(), "{}");
+    }
+
+    #[test]
+    fn it_rejects_an_empty_read_buffer() {
+        let result = ClientBuilder::new().read_buffer_size(0).build();
+        assert!(matches!(
+            result,
+            Err(ClientError::EngineError(EngineResult::IllegalArgument))
+        ));
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+    sync::RwLock,
+};
+
+use cookie_store::{CookieStore, RawCookie};
+use http::HeaderValue;
+use url::Url;
+
+/// Stores cookies received by a [super::Client] and sends them with later requests.
+///
+/// Cookies are captured from every response, including redirects that are followed,
+/// and are only sent to URLs that match their domain, path, secure flag and expiry.
+/// A jar can be shared between clients.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use cronet_rs::client::{Client, CookieJar};
+///
+/// let jar = Arc::new(CookieJar::load("cookies.json").unwrap_or_default());
+/// let client = Client::builder().cookie_jar(jar.clone()).build().unwrap();
+/// // ...
+/// jar.save("cookies.json").unwrap();
+/// ```
+pub struct CookieJar {
+    store: RwLock<CookieStore>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self {
+            store: RwLock::new(CookieStore::default()),
+        }
+    }
+
+    /// Loads a jar from a file written by `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let store = CookieStore::load_json(reader).map_err(io::Error::other)?;
+        Ok(Self {
+            store: RwLock::new(store),
+        })
+    }
+
+    /// Saves the cookies to a file as JSON.
+    /// Session cookies and expired cookies aren't saved.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.store
+            .read()
+            .unwrap()
+            .save_json(&mut writer)
+            .map_err(io::Error::other)?;
+        writer.flush()
+    }
+
+    /// Adds a cookie in the `Set-Cookie` format as if it was received from `url`.
+    /// Invalid cookies and URLs are ignored.
+    pub fn add_cookie_str(&self, cookie: &str, url: &str) {
+        self.store_response_cookies(url, [cookie.to_string()].into_iter());
+    }
+
+    /// Removes all cookies.
+    pub fn clear(&self) {
+        self.store.write().unwrap().clear();
+    }
+
+    /// Stores the cookies of the `Set-Cookie` header values received from `url`.
+    pub(crate) fn store_response_cookies(&self, url: &str, values: impl Iterator<Item = String>) {
+        let Ok(url) = Url::parse(url) else {
+            return;
+        };
+        let cookies = values.filter_map(|value| RawCookie::parse(value).ok());
+        self.store
+            .write()
+            .unwrap()
+            .store_response_cookies(cookies, &url);
+    }
+
+    /// Returns the value of the `Cookie` header for a request to `url`,
+    /// or `None` if no cookies match.
+    pub(crate) fn cookie_header(&self, url: &str) -> Option<HeaderValue> {
+        let url = Url::parse(url).ok()?;
+        let store = self.store.read().unwrap();
+        let value = store
+            .get_request_values(&url)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if value.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&value).ok()
+    }
+}
+
+impl Default for CookieJar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CookieJar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let store = self.store.read().unwrap();
+        f.debug_struct("CookieJar")
+            .field("cookies", &store.iter_unexpired().count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::CookieJar;
+
+    #[test]
+    fn it_matches_cookies() {
+        let jar = CookieJar::new();
+        jar.add_cookie_str("a=1; Domain=example.com", "https://www.example.com/");
+        jar.add_cookie_str("b=2; Path=/api", "https://example.com/");
+        jar.add_cookie_str("c=3; Secure", "https://example.com/");
+        jar.add_cookie_str("d=4; Max-Age=0", "https://example.com/");
+
+        let cookies = |url| {
+            let header = jar.cookie_header(url);
+            let mut cookies: Vec<String> = header
+                .iter()
+                .flat_map(|v| v.to_str().unwrap().split("; "))
+                .map(String::from)
+                .collect();
+            cookies.sort();
+            cookies
+        };
+        assert_eq!(cookies("https://example.com/"), ["a=1", "c=3"]);
+        assert_eq!(cookies("https://sub.example.com/api/x"), ["a=1"]);
+        assert_eq!(cookies("https://example.com/api/x"), ["a=1", "b=2", "c=3"]);
+        assert_eq!(cookies("http://example.com/"), ["a=1"]);
+        assert!(cookies("https://other.com/").is_empty());
+    }
+
+    #[test]
+    fn it_saves_and_loads_cookies() {
+        let path =
+            std::env::temp_dir().join(format!("cronet-rs-cookie-jar-test-{}", std::process::id()));
+        let jar = CookieJar::new();
+        jar.add_cookie_str("persistent=1; Max-Age=3600", "https://example.com/");
+        jar.add_cookie_str("session=1", "https://example.com/");
+        jar.save(&path).unwrap();
+
+        let jar = CookieJar::load(&path).unwrap();
+        let header = jar.cookie_header("https://example.com/").unwrap();
+        assert_eq!(header, "persistent=1");
+
+        fs::remove_file(path).unwrap();
+    }
+}
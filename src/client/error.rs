@@ -1,28 +1,74 @@
 use core::fmt;
+use std::error::Error;
 
-use crate::{CronetError, EngineResult};
+use crate::{CronetError, EngineResult, ErrorCode, UrlRequestStatus};
+
+use super::{RetryHistory, TimeoutPhase};
 
 pub enum ClientError {
     /// Internal cronet error.
-    CronetError(CronetError),
+    CronetError(ErrorInfo),
     /// The request was cancelled.
     CancellationError,
     /// Unexpected cronet engine result.
     EngineError(EngineResult),
+    /// The request timed out and was cancelled.
+    Timeout {
+        /// The phase the request was in when it timed out.
+        phase: TimeoutPhase,
+        /// The status of the request when it timed out, if it could be queried.
+        status: Option<UrlRequestStatus>,
+    },
+    /// The redirect limit of the [super::RedirectPolicy] was exceeded.
+    TooManyRedirects(usize),
+    /// The request was redirected back to the contained URL, which it had already visited.
+    RedirectLoop(String),
+    /// The [super::RedirectPolicy] failed the request.
+    RedirectError(Box<dyn Error + Send + Sync>),
+    /// The response can't be represented as a [http::Response].
+    InvalidResponse(ResponseError),
+    /// A handler of the request panicked, e.g. the [super::RedirectPolicy] or a response body sink.
+    /// Contains the panic message.
+    HandlerPanicked(String),
+}
+
+impl ClientError {
+    /// Returns the failed attempts before the last one if the request was retried (see [super::RetryPolicy])
+    /// and the last attempt failed with a Cronet error as well.
+    pub fn retry_history(&self) -> Option<&RetryHistory> {
+        match self {
+            Self::CronetError(error) if !error.retry_history.attempts.is_empty() => {
+                Some(&error.retry_history)
+            }
+            _ => None,
+        }
+    }
 }
 
-impl From<CronetError> for ClientError {
-    fn from(error: CronetError) -> Self {
+impl From<ErrorInfo> for ClientError {
+    fn from(error: ErrorInfo) -> Self {
         Self::CronetError(error)
     }
 }
 
+impl From<ResponseError> for ClientError {
+    fn from(error: ResponseError) -> Self {
+        Self::InvalidResponse(error)
+    }
+}
+
 impl fmt::Display for ClientError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::CronetError(error) => write!(f, "{}", error),
             Self::CancellationError => write!(f, "Request was cancelled"),
             Self::EngineError(error) => write!(f, "Unexpected engine result: {:?}", error),
+            Self::Timeout { phase, status } => fmt_timeout(f, phase, status),
+            Self::TooManyRedirects(max) => write!(f, "Too many redirects (limit: {})", max),
+            Self::RedirectLoop(url) => write!(f, "Redirect loop at {}", url),
+            Self::RedirectError(error) => write!(f, "Redirect error: {}", error),
+            Self::InvalidResponse(error) => write!(f, "Invalid response: {}", error),
+            Self::HandlerPanicked(message) => write!(f, "Handler panicked: {}", message),
         }
     }
 }
@@ -33,6 +79,124 @@ impl fmt::Debug for ClientError {
             Self::CronetError(error) => write!(f, "{}", error),
             Self::CancellationError => write!(f, "Request was cancelled"),
             Self::EngineError(error) => write!(f, "Unexpected engine result: {:?}", error),
+            Self::Timeout { phase, status } => fmt_timeout(f, phase, status),
+            Self::TooManyRedirects(max) => write!(f, "Too many redirects (limit: {})", max),
+            Self::RedirectLoop(url) => write!(f, "Redirect loop at {}", url),
+            Self::RedirectError(error) => write!(f, "Redirect error: {}", error),
+            Self::InvalidResponse(error) => write!(f, "Invalid response: {}", error),
+            Self::HandlerPanicked(message) => write!(f, "Handler panicked: {}", message),
+        }
+    }
+}
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::CronetError(error) => Some(error),
+            Self::RedirectError(error) => Some(error.as_ref()),
+            Self::InvalidResponse(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// An owned copy of the [CronetError] a request failed with,
+/// which can be used after the request is destroyed and sent across threads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorInfo {
+    pub error_code: ErrorCode,
+    pub message: String,
+    /// See [CronetError::internal_error_code].
+    pub internal_error_code: i32,
+    /// See [CronetError::retryable].
+    pub retryable: bool,
+    /// See [CronetError::quic_detailed_error_code].
+    pub quic_detailed_error_code: i32,
+    /// The failed attempts before this one, if the request was retried (see [super::RetryPolicy]).
+    pub retry_history: RetryHistory,
+}
+
+impl From<&CronetError> for ErrorInfo {
+    fn from(error: &CronetError) -> Self {
+        Self {
+            error_code: error.error_code(),
+            message: error.message().into_owned(),
+            internal_error_code: error.internal_error_code(),
+            retryable: error.retryable(),
+            quic_detailed_error_code: error.quic_detailed_error_code(),
+            retry_history: RetryHistory::default(),
+        }
+    }
+}
+
+impl fmt::Display for ErrorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CronetError {{ code: {:?}, message: {:?} }}",
+            self.error_code, self.message
+        )
+    }
+}
+
+impl Error for ErrorInfo {}
+
+/// Error when converting a [crate::UrlResponseInfo] into a [http::Response].
+#[derive(Debug)]
+pub enum ResponseError {
+    /// The response has a status code outside of the valid range.
+    InvalidStatusCode(i32),
+    /// The response contains a header with an invalid name.
+    InvalidHeaderName(String),
+    /// The response contains a header with an invalid value. Contains the header name.
+    InvalidHeaderValue(String),
+}
+
+impl fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidStatusCode(code) => write!(f, "Invalid status code {}", code),
+            Self::InvalidHeaderName(name) => write!(f, "Invalid header name '{}'", name),
+            Self::InvalidHeaderValue(name) => write!(f, "Invalid value for header '{}'", name),
         }
     }
 }
+
+impl Error for ResponseError {}
+
+fn fmt_timeout(
+    f: &mut fmt::Formatter<'_>,
+    phase: &TimeoutPhase,
+    status: &Option<UrlRequestStatus>,
+) -> fmt::Result {
+    let phase = match phase {
+        TimeoutPhase::Headers => "waiting for the response headers",
+        TimeoutPhase::Body => "reading the response body",
+    };
+    match status {
+        Some(status) => write!(f, "Request timed out while {} ({:?})", phase, status),
+        None => write!(f, "Request timed out while {}", phase),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use crate::{CronetError, ErrorCode};
+
+    use super::{ClientError, ErrorInfo};
+
+    #[test]
+    fn it_is_a_thread_safe_error() {
+        let error = CronetError::new();
+        error.set_error_code(ErrorCode::ConnectionReset);
+        error.set_message("net::ERR_CONNECTION_RESET");
+
+        let error = ClientError::from(ErrorInfo::from(&error));
+        let boxed: Box<dyn Error + Send + Sync> = Box::new(error);
+        let source = boxed.source().unwrap().downcast_ref::<ErrorInfo>().unwrap();
+        assert_eq!(source.error_code, ErrorCode::ConnectionReset);
+        assert_eq!(source.message, "net::ERR_CONNECTION_RESET");
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use crate::{Executor, Runnable};
+
+/// Built-in [Executor] that runs the callbacks of a [super::Client]
+/// (see [super::ClientBuilder::executor_kind]).
+///
+/// The default is a thread pool with one thread per available CPU.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExecutorKind {
+    /// Runs callbacks on a fixed number of worker threads.
+    ThreadPool(usize),
+
+    /// Runs callbacks one after another on a single thread, in the order they were posted.
+    Serial,
+
+    /// Runs callbacks directly on the thread that posts them, which is usually the network thread of Cronet.
+    /// Requests are sent with [crate::UrlRequestParams::set_allow_direct_executor].
+    ///
+    /// **Warning:** Callbacks must not block, as that blocks the network thread.
+    /// This includes reading the bodies of uploads, e.g. from a [std::fs::File].
+    Direct,
+}
+
+impl ExecutorKind {
+    /// Creates the executor, including its worker threads.
+    pub(crate) fn create(self) -> Executor {
+        match self {
+            ExecutorKind::ThreadPool(size) => spawn_workers(size.max(1)),
+            ExecutorKind::Serial => spawn_workers(1),
+            ExecutorKind::Direct => Executor::new(|_, runnable| runnable.run()),
+        }
+    }
+
+    /// Whether requests must allow Cronet to run callbacks directly on the network thread.
+    pub(crate) fn is_direct(self) -> bool {
+        self == ExecutorKind::Direct
+    }
+}
+
+impl Default for ExecutorKind {
+    fn default() -> Self {
+        let size = thread::available_parallelism().map_or(4, NonZeroUsize::get);
+        ExecutorKind::ThreadPool(size)
+    }
+}
+
+/// Creates an executor that queues runnables for `size` worker threads.
+/// The workers stop once the executor is dropped and the queued runnables ran.
+fn spawn_workers(size: usize) -> Executor {
+    let (tx, rx) = mpsc::channel::<Runnable>();
+    let rx = Arc::new(Mutex::new(rx));
+    for i in 0..size {
+        let rx = rx.clone();
+        thread::Builder::new()
+            .name(format!("cronet-executor-{}", i))
+            .spawn(move || loop {
+                let Ok(runnable) = rx.lock().unwrap().recv() else {
+                    break;
+                };
+                runnable.run();
+            })
+            .expect("Failed to spawn executor thread");
+    }
+
+    let tx = Mutex::new(tx);
+    Executor::new(move |_, runnable| {
+        let _ = tx.lock().unwrap().send(runnable);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::Runnable;
+
+    use super::ExecutorKind;
+
+    #[test]
+    fn it_runs_runnables_on_worker_threads() {
+        let executor = ExecutorKind::ThreadPool(2).create();
+        let runs = Arc::new(AtomicUsize::new(0));
+        for _ in 0..4 {
+            let runs = runs.clone();
+            executor.execute(Runnable::new(move |_| {
+                runs.fetch_add(1, Ordering::SeqCst);
+            }));
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while runs.load(Ordering::SeqCst) < 4 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 4);
+    }
+}
@@ -1,12 +1,44 @@
+#[cfg(feature = "async")]
+mod async_client;
 mod body;
+mod body_stream;
 mod body_upload_provider;
 #[allow(clippy::module_inception)]
 mod client;
+mod client_builder;
+#[cfg(feature = "cookies")]
+mod cookie_jar;
 mod error;
+mod executors;
+mod pending_request;
+mod redirect;
+mod request_handle;
+mod request_template;
 mod response_handler;
+mod response_info;
+mod retry;
+#[cfg(feature = "tower")]
+mod service;
+mod timeout;
 
+#[cfg(feature = "async")]
+pub use async_client::*;
 pub use body::*;
+pub use body_stream::*;
 pub use body_upload_provider::*;
 pub use client::*;
+pub use client_builder::*;
+#[cfg(feature = "cookies")]
+pub use cookie_jar::*;
 pub use error::*;
+pub use executors::*;
+pub use pending_request::*;
+pub use redirect::*;
+pub(crate) use request_handle::*;
+pub use request_template::*;
 pub use response_handler::*;
+pub use response_info::*;
+pub use retry::*;
+#[cfg(feature = "tower")]
+pub use service::*;
+pub use timeout::*;
@@ -0,0 +1,89 @@
+use std::sync::mpsc::Receiver;
+
+use super::{Body, ClientError, RequestHandle, Status};
+
+/// A request started by [super::Client::send_cancellable].
+///
+/// ```no_run
+/// use std::{thread, time::Duration};
+/// use cronet_rs::client::{Body, Client};
+///
+/// let client = Client::new();
+/// let request = http::Request::get("https://example.com/large-file")
+///     .body(Body::default())
+///     .unwrap();
+/// let pending = client.send_cancellable(request).unwrap();
+///
+/// let token = pending.cancel_token();
+/// thread::spawn(move || {
+///     thread::sleep(Duration::from_secs(5));
+///     token.cancel();
+/// });
+///
+/// let result = pending.wait();
+/// ```
+pub struct PendingRequest {
+    pub(crate) rx: Receiver<Status>,
+    pub(crate) handle: RequestHandle,
+}
+
+impl PendingRequest {
+    /// Returns a token to cancel the request from any thread.
+    pub fn cancel_token(&self) -> CancelToken {
+        CancelToken {
+            handle: self.handle.clone(),
+        }
+    }
+
+    /// Blocks until the request finishes.
+    /// Returns [ClientError::CancellationError] if the request was cancelled.
+    pub fn wait(self) -> Result<http::Response<Body>, ClientError> {
+        match self.rx.recv() {
+            Ok(status) => status.into_result(),
+            Err(_) => Err(ClientError::CancellationError),
+        }
+    }
+}
+
+/// Cancels the request of a [PendingRequest].
+///
+/// Cancelling is a no-op once the request has finished.
+#[derive(Clone)]
+pub struct CancelToken {
+    handle: RequestHandle,
+}
+
+impl CancelToken {
+    /// Cancels the request if it is still in flight.
+    pub fn cancel(&self) {
+        self.handle.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use crate::{
+        client::{ClientError, RequestHandle, Status},
+        UrlRequest,
+    };
+
+    use super::PendingRequest;
+
+    #[test]
+    fn it_cancels_finished_requests() {
+        let (tx, rx) = mpsc::channel();
+        let handle = RequestHandle::new(UrlRequest::new());
+        let pending = PendingRequest { rx, handle };
+        let token = pending.cancel_token();
+
+        pending.handle.release();
+        token.cancel();
+        tx.send(Status::Canceled).unwrap();
+        assert!(matches!(
+            pending.wait(),
+            Err(ClientError::CancellationError)
+        ));
+    }
+}
@@ -0,0 +1,238 @@
+use std::{error::Error, fmt};
+
+use http::StatusCode;
+
+/// Determines whether a [super::Client] follows redirects.
+///
+/// The default policy follows up to 10 redirects.
+///
+/// ```no_run
+/// use cronet_rs::client::{Client, RedirectPolicy};
+///
+/// let client = Client::builder()
+///     .redirect_policy(RedirectPolicy::custom(|attempt| {
+///         if attempt.url().starts_with("https://") {
+///             attempt.follow()
+///         } else {
+///             attempt.stop()
+///         }
+///     }))
+///     .build()
+///     .unwrap();
+/// ```
+pub struct RedirectPolicy {
+    kind: PolicyKind,
+}
+
+enum PolicyKind {
+    Limited(usize),
+    None,
+    Custom(Box<dyn Fn(Attempt) -> Action + Send + Sync>),
+}
+
+impl RedirectPolicy {
+    /// Follows up to `max` redirects.
+    /// Exceeding the limit fails the request with [super::ClientError::TooManyRedirects].
+    /// A redirect to a URL that was already visited fails the request with [super::ClientError::RedirectLoop].
+    pub fn limited(max: usize) -> Self {
+        Self {
+            kind: PolicyKind::Limited(max),
+        }
+    }
+
+    /// Doesn't follow any redirects.
+    /// The redirect response, including its `Location` header, is returned instead.
+    pub fn none() -> Self {
+        Self {
+            kind: PolicyKind::None,
+        }
+    }
+
+    /// Decides for each redirect whether to follow it, stop at it or fail the request.
+    pub fn custom<F>(policy: F) -> Self
+    where
+        F: Fn(Attempt) -> Action + Send + Sync + 'static,
+    {
+        Self {
+            kind: PolicyKind::Custom(Box::new(policy)),
+        }
+    }
+
+    /// Applies the policy to a redirect to `next`.
+    pub(crate) fn redirect(&self, status: StatusCode, next: &str, previous: &[String]) -> Action {
+        let attempt = Attempt {
+            status,
+            next,
+            previous,
+        };
+        // `previous` includes the original URL, so this is redirect number `previous.len()`.
+        match &self.kind {
+            PolicyKind::Limited(max) if previous.len() > *max => Action {
+                kind: ActionKind::TooManyRedirects(*max),
+            },
+            PolicyKind::Limited(_) if previous.iter().any(|url| url == next) => Action {
+                kind: ActionKind::RedirectLoop(next.to_string()),
+            },
+            PolicyKind::Limited(_) => attempt.follow(),
+            PolicyKind::None => attempt.stop(),
+            PolicyKind::Custom(policy) => policy(attempt),
+        }
+    }
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self::limited(10)
+    }
+}
+
+impl fmt::Debug for RedirectPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            PolicyKind::Limited(max) => f.debug_tuple("Limited").field(&max).finish(),
+            PolicyKind::None => f.write_str("None"),
+            PolicyKind::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+/// A redirect the [RedirectPolicy] has to decide on.
+#[derive(Debug)]
+pub struct Attempt<'a> {
+    status: StatusCode,
+    next: &'a str,
+    previous: &'a [String],
+}
+
+impl<'a> Attempt<'a> {
+    /// Returns the status code of the redirect response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the URL to redirect to.
+    pub fn url(&self) -> &str {
+        self.next
+    }
+
+    /// Returns the URLs visited so far, starting with the URL of the original request.
+    pub fn previous(&self) -> &[String] {
+        self.previous
+    }
+
+    /// Follows the redirect.
+    pub fn follow(self) -> Action {
+        Action {
+            kind: ActionKind::Follow,
+        }
+    }
+
+    /// Stops at the redirect and returns the redirect response.
+    pub fn stop(self) -> Action {
+        Action {
+            kind: ActionKind::Stop,
+        }
+    }
+
+    /// Fails the request with [super::ClientError::RedirectError].
+    pub fn error<E: Into<Box<dyn Error + Send + Sync>>>(self, error: E) -> Action {
+        Action {
+            kind: ActionKind::Error(error.into()),
+        }
+    }
+}
+
+/// The decision of a [RedirectPolicy] (see [Attempt]).
+#[derive(Debug)]
+pub struct Action {
+    pub(crate) kind: ActionKind,
+}
+
+#[derive(Debug)]
+pub(crate) enum ActionKind {
+    Follow,
+    Stop,
+    Error(Box<dyn Error + Send + Sync>),
+    TooManyRedirects(usize),
+    RedirectLoop(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use http::StatusCode;
+
+    use super::{ActionKind, RedirectPolicy};
+
+    fn chain(len: usize) -> Vec<String> {
+        (0..len)
+            .map(|i| format!("https://example.com/{}", i))
+            .collect()
+    }
+
+    #[test]
+    fn it_limits_redirects() {
+        let policy = RedirectPolicy::limited(2);
+        let next = "https://example.com/next";
+
+        let action = policy.redirect(StatusCode::FOUND, next, &chain(2));
+        assert!(matches!(action.kind, ActionKind::Follow));
+
+        let action = policy.redirect(StatusCode::FOUND, next, &chain(3));
+        assert!(matches!(action.kind, ActionKind::TooManyRedirects(2)));
+    }
+
+    #[test]
+    fn it_follows_at_most_max_redirects() {
+        let next = "https://example.com/next";
+
+        let action = RedirectPolicy::limited(1).redirect(StatusCode::FOUND, next, &chain(1));
+        assert!(matches!(action.kind, ActionKind::Follow));
+        let action = RedirectPolicy::limited(1).redirect(StatusCode::FOUND, next, &chain(2));
+        assert!(matches!(action.kind, ActionKind::TooManyRedirects(1)));
+        let action = RedirectPolicy::limited(0).redirect(StatusCode::FOUND, next, &chain(1));
+        assert!(matches!(action.kind, ActionKind::TooManyRedirects(0)));
+    }
+
+    #[test]
+    fn it_detects_redirect_loops() {
+        let policy = RedirectPolicy::limited(10);
+        let action = policy.redirect(StatusCode::FOUND, "https://example.com/0", &chain(2));
+        match action.kind {
+            ActionKind::RedirectLoop(url) => assert_eq!(url, "https://example.com/0"),
+            other => panic!("unexpected action: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_stops_at_redirects() {
+        let policy = RedirectPolicy::none();
+        let action = policy.redirect(StatusCode::FOUND, "https://example.com", &chain(1));
+        assert!(matches!(action.kind, ActionKind::Stop));
+    }
+
+    #[test]
+    fn it_applies_custom_policies() {
+        let policy = RedirectPolicy::custom(|attempt| {
+            if attempt.status() == StatusCode::MOVED_PERMANENTLY {
+                attempt.error("moved permanently")
+            } else if attempt.previous().len() > 1 {
+                attempt.stop()
+            } else {
+                attempt.follow()
+            }
+        });
+        let next = "https://example.com/next";
+
+        let action = policy.redirect(StatusCode::FOUND, next, &chain(1));
+        assert!(matches!(action.kind, ActionKind::Follow));
+
+        let action = policy.redirect(StatusCode::FOUND, next, &chain(2));
+        assert!(matches!(action.kind, ActionKind::Stop));
+
+        let action = policy.redirect(StatusCode::MOVED_PERMANENTLY, next, &chain(1));
+        match action.kind {
+            ActionKind::Error(error) => assert_eq!(error.to_string(), "moved permanently"),
+            other => panic!("unexpected action: {:?}", other),
+        }
+    }
+}
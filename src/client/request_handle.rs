@@ -0,0 +1,326 @@
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    unwind, Buffer, Engine, EngineResult, Executor, UrlRequest, UrlRequestCallback,
+    UrlRequestParams, UrlRequestStatus, UrlRequestStatusListener,
+};
+
+use super::{Body, ClientCore, ClientError, TimeoutPhase};
+
+/// Why the client cancelled a request.
+#[derive(Debug)]
+pub(crate) enum CancelReason {
+    Timeout(TimeoutPhase, Option<UrlRequestStatus>),
+    /// A redirect was not followed, so the redirect response is the final response.
+    Redirect,
+    /// The request is sent again as the given request, see [super::RequestTemplate].
+    Reissue(Box<http::Request<Body>>),
+    Failed(ClientError),
+}
+
+struct State {
+    /// The request, which is shared with callers that use it outside the lock.
+    request: Option<Arc<UrlRequest>>,
+    /// The callback of the request, which is kept when the request is replaced.
+    callback: Option<UrlRequestCallback>,
+    cancel_reason: Option<CancelReason>,
+    /// Whether the request was cancelled through `cancel`.
+    canceled: bool,
+    phase: TimeoutPhase,
+    /// Since when the request is waiting on the network.
+    /// `None` while it waits for the consumer of a streamed body instead.
+    idle_since: Option<Instant>,
+}
+
+/// Result of [RequestHandle::check_timeout].
+#[derive(Debug, PartialEq)]
+pub(crate) enum TimeoutCheck {
+    /// The request finished, so it no longer needs to be watched.
+    Finished,
+    /// A timeout expired while the request was in the given phase.
+    Expired(TimeoutPhase),
+    /// No timeout expired yet. The timeouts should be checked again at the given time.
+    Pending(Option<Instant>),
+}
+
+/// Shared handle that owns an in-flight [UrlRequest] and its [UrlRequestCallback].
+///
+/// The [super::ResponseHandler] releases the request once it finishes, which drops it,
+/// so cancelling through a handle is safe at any time, even after the request has finished.
+///
+/// Cronet is never called while the state is locked, as it may invoke callbacks that use the handle.
+#[derive(Clone)]
+pub(crate) struct RequestHandle {
+    state: Arc<Mutex<State>>,
+    /// The engine and executor of the client, which must outlive the request.
+    /// Declared after `state`, so the request is dropped first.
+    core: Option<Arc<ClientCore>>,
+}
+
+impl RequestHandle {
+    pub(crate) fn new(request: UrlRequest) -> Self {
+        let state = State {
+            request: Some(Arc::new(request)),
+            callback: None,
+            cancel_reason: None,
+            canceled: false,
+            phase: TimeoutPhase::Headers,
+            idle_since: Some(Instant::now()),
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            core: None,
+        }
+    }
+
+    /// Keeps the engine and executor of the client alive as long as the request,
+    /// even if the client itself is dropped in the meantime.
+    pub(crate) fn with_core(mut self, core: Arc<ClientCore>) -> Self {
+        self.core = Some(core);
+        self
+    }
+
+    /// Locks the state, even if a callback panicked while holding the lock.
+    fn lock(&self) -> MutexGuard<'_, State> {
+        unwind::lock(&self.state)
+    }
+
+    /// Returns the request, unless it was released.
+    fn request(&self) -> Option<Arc<UrlRequest>> {
+        self.lock().request.clone()
+    }
+
+    /// Initializes the request, and keeps `callback` alive until the request is released.
+    pub(crate) fn init(
+        &self,
+        engine: &Engine,
+        url: &str,
+        params: &UrlRequestParams,
+        callback: UrlRequestCallback,
+        executor: &Executor,
+    ) -> EngineResult {
+        let Some(request) = self.request() else {
+            return EngineResult::IllegalState;
+        };
+        let result = request.init_with_params(engine, url, params, &callback, executor);
+        let old = self.lock().callback.replace(callback);
+        drop(old);
+        result
+    }
+
+    /// Starts the request.
+    pub(crate) fn start(&self) -> EngineResult {
+        match self.request() {
+            Some(request) => request.start(),
+            None => EngineResult::IllegalState,
+        }
+    }
+
+    /// Cancels the request if it is still in flight.
+    /// Also prevents the request from being replaced later, e.g. when it is retried.
+    pub(crate) fn cancel(&self) {
+        let request = {
+            let mut state = self.lock();
+            state.canceled = true;
+            state.request.clone()
+        };
+        if let Some(request) = request {
+            request.cancel();
+        }
+    }
+
+    /// Cancels the request if it is still in flight and records why.
+    /// The reason is only recorded for the first cancellation.
+    pub(crate) fn cancel_with(&self, reason: CancelReason) {
+        let request = {
+            let mut state = self.lock();
+            if state.request.is_some() && state.cancel_reason.is_none() {
+                state.cancel_reason = Some(reason);
+            }
+            state.request.clone()
+        };
+        if let Some(request) = request {
+            request.cancel();
+        }
+    }
+
+    /// Takes the reason the request was cancelled by the client, if any.
+    pub(crate) fn take_cancel_reason(&self) -> Option<CancelReason> {
+        self.lock().cancel_reason.take()
+    }
+
+    /// Reads the next part of the response body into a new buffer of the given size.
+    /// Does nothing if the request already finished.
+    ///
+    /// If Cronet refuses to read, the request is cancelled and fails with [ClientError::EngineError].
+    pub(crate) fn read(&self, buffer_size: u64) {
+        let request = {
+            let mut state = self.lock();
+            if state.request.is_some() {
+                state.idle_since = Some(Instant::now());
+            }
+            state.request.clone()
+        };
+        let Some(request) = request else {
+            return;
+        };
+        let result = request.read(Buffer::new_with_size(buffer_size));
+        if result != EngineResult::Success {
+            self.cancel_with(CancelReason::Failed(ClientError::EngineError(result)));
+        }
+    }
+
+    /// Queries the status of the request.
+    /// Returns `false` if the request already finished, in which case the listener isn't invoked.
+    pub(crate) fn status(&self, listener: UrlRequestStatusListener) -> bool {
+        match self.request() {
+            Some(request) => {
+                request.status(listener);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Records that the request made progress and keeps waiting on the network.
+    pub(crate) fn touch(&self) {
+        self.lock().idle_since = Some(Instant::now());
+    }
+
+    /// Records that the request waits for the consumer of its response body.
+    pub(crate) fn pause(&self) {
+        self.lock().idle_since = None;
+    }
+
+    /// Records that the response headers were received.
+    pub(crate) fn set_phase(&self, phase: TimeoutPhase) {
+        self.lock().phase = phase;
+    }
+
+    /// Checks whether one of the timeouts expired at `now`.
+    ///
+    /// While the request waits for the consumer of its body, the read timeout can't expire
+    /// before it has passed from `now`, which is when it should be checked again.
+    pub(crate) fn check_timeout(
+        &self,
+        deadline: Option<Instant>,
+        read_timeout: Option<Duration>,
+        now: Instant,
+    ) -> TimeoutCheck {
+        let state = self.lock();
+        if state.request.is_none() {
+            return TimeoutCheck::Finished;
+        }
+
+        let idle_deadline =
+            read_timeout.map(|read_timeout| state.idle_since.unwrap_or(now) + read_timeout);
+        let next = match (deadline, idle_deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        match next {
+            Some(next) if next <= now => TimeoutCheck::Expired(state.phase),
+            next => TimeoutCheck::Pending(next),
+        }
+    }
+
+    /// Replaces the current request with a new one, which drops the current request.
+    /// Returns `false` if the current request was cancelled, in which case the new request is dropped instead.
+    pub(crate) fn replace(&self, request: UrlRequest) -> bool {
+        let mut state = self.lock();
+        if state.canceled {
+            drop(state);
+            drop(request);
+            return false;
+        }
+        let old = state.request.replace(Arc::new(request));
+        state.cancel_reason = None;
+        state.idle_since = Some(Instant::now());
+        drop(state);
+        drop(old);
+        true
+    }
+
+    /// Marks the request as finished and drops it along with its callback.
+    pub(crate) fn release(&self) {
+        let (request, callback) = {
+            let mut state = self.lock();
+            (state.request.take(), state.callback.take())
+        };
+        drop(request);
+        drop(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{
+        client::{ClientError, TimeoutPhase},
+        UrlRequest,
+    };
+
+    use super::{CancelReason, RequestHandle, TimeoutCheck};
+
+    fn handle() -> RequestHandle {
+        RequestHandle::new(UrlRequest::new())
+    }
+
+    #[test]
+    fn it_expires_the_read_timeout() {
+        let handle = handle();
+        handle.set_phase(TimeoutPhase::Body);
+        let read_timeout = Some(Duration::from_millis(10));
+        let now = Instant::now();
+        let check = handle.check_timeout(None, read_timeout, now);
+        assert!(matches!(check, TimeoutCheck::Pending(Some(_))));
+        let check = handle.check_timeout(None, read_timeout, now + Duration::from_millis(20));
+        assert_eq!(check, TimeoutCheck::Expired(TimeoutPhase::Body));
+    }
+
+    #[test]
+    fn it_ignores_the_read_timeout_while_paused() {
+        let handle = handle();
+        handle.pause();
+        let now = Instant::now() + Duration::from_secs(1);
+        let deadline = now + Duration::from_millis(50);
+        let read_timeout = Some(Duration::from_millis(10));
+        let check = handle.check_timeout(Some(deadline), read_timeout, now);
+        assert_eq!(
+            check,
+            TimeoutCheck::Pending(Some(now + Duration::from_millis(10)))
+        );
+        let check = handle.check_timeout(Some(deadline), read_timeout, deadline);
+        assert_eq!(check, TimeoutCheck::Expired(TimeoutPhase::Headers));
+    }
+
+    #[test]
+    fn it_stops_checking_once_released() {
+        let handle = handle();
+        handle.release();
+        let check = handle.check_timeout(None, None, Instant::now());
+        assert_eq!(check, TimeoutCheck::Finished);
+    }
+
+    #[test]
+    fn it_fails_requests_that_cannot_read() {
+        let handle = handle();
+        handle.read(512);
+        assert!(matches!(
+            handle.take_cancel_reason(),
+            Some(CancelReason::Failed(ClientError::EngineError(_)))
+        ));
+    }
+
+    #[test]
+    fn it_is_not_replaced_once_cancelled() {
+        let handle = handle();
+        handle.release();
+        handle.cancel();
+        assert!(!handle.replace(UrlRequest::new()));
+    }
+}
@@ -0,0 +1,317 @@
+use std::{io, sync::Arc};
+
+use http::{
+    header::{self, HeaderName},
+    HeaderMap, Method, StatusCode, Uri,
+};
+
+#[cfg(feature = "cookies")]
+use super::CookieJar;
+use super::{Body, ClientCore, ClientError, SharedRewind};
+
+/// Headers that are removed from requests redirected to another origin by default.
+pub const DEFAULT_SENSITIVE_HEADERS: [HeaderName; 3] = [
+    header::AUTHORIZATION,
+    header::COOKIE,
+    header::PROXY_AUTHORIZATION,
+];
+
+/// Headers that describe the request body, removed when a redirect drops the body.
+const CONTENT_HEADERS: [HeaderName; 5] = [
+    header::CONTENT_TYPE,
+    header::CONTENT_LENGTH,
+    header::CONTENT_ENCODING,
+    header::CONTENT_LANGUAGE,
+    header::CONTENT_LOCATION,
+];
+
+/// Everything that is needed to send a request again, e.g. to another origin after a redirect.
+pub(crate) struct RequestTemplate {
+    pub(crate) core: Arc<ClientCore>,
+    /// Whether requests may run callbacks directly on the network thread.
+    pub(crate) allow_direct_executor: bool,
+    method: Method,
+    headers: HeaderMap,
+    rewind: Option<SharedRewind>,
+    sensitive_headers: Arc<Vec<HeaderName>>,
+    /// The jar that manages the `Cookie` header, if any.
+    #[cfg(feature = "cookies")]
+    cookie_jar: Option<Arc<CookieJar>>,
+}
+
+impl RequestTemplate {
+    /// Creates a template for the given request.
+    /// The body of the request is made rewindable through the template, so it can be sent again.
+    pub(crate) fn new(
+        core: Arc<ClientCore>,
+        request: http::Request<Body>,
+        sensitive_headers: Arc<Vec<HeaderName>>,
+    ) -> (Self, http::Request<Body>) {
+        let (parts, mut body) = request.into_parts();
+
+        let rewind = body.share_rewind();
+
+        let template = Self {
+            core,
+            allow_direct_executor: false,
+            method: parts.method.clone(),
+            headers: parts.headers.clone(),
+            rewind,
+            sensitive_headers,
+            #[cfg(feature = "cookies")]
+            cookie_jar: None,
+        };
+        (template, http::Request::from_parts(parts, body))
+    }
+
+    /// Lets `jar` manage the `Cookie` header of the request and of any request sent after a redirect.
+    /// Adds the cookies of the jar that match the URL of the request.
+    #[cfg(feature = "cookies")]
+    pub(crate) fn set_cookie_jar(
+        &mut self,
+        jar: Arc<CookieJar>,
+        request: &mut http::Request<Body>,
+    ) {
+        if let Some(cookies) = jar.cookie_header(&request.uri().to_string()) {
+            self.headers.insert(header::COOKIE, cookies.clone());
+            request.headers_mut().insert(header::COOKIE, cookies);
+        }
+        self.cookie_jar = Some(jar);
+    }
+
+    /// Returns whether the template may send requests again after a redirect.
+    pub(crate) fn is_active(&self) -> bool {
+        #[cfg(feature = "cookies")]
+        if self.cookie_jar.is_some() {
+            return true;
+        }
+        !self.sensitive_headers.is_empty()
+    }
+
+    /// Returns the request to send instead of following a redirect from `from` to `to`,
+    /// or `None` if the redirect can be followed as is.
+    ///
+    /// A request is sent again if the redirect points to another origin
+    /// and the request contains any of the sensitive headers, which are removed from it,
+    /// or if the cookie jar has different cookies for the new location.
+    pub(crate) fn redirect(
+        &mut self,
+        status: StatusCode,
+        from: &str,
+        to: &str,
+    ) -> Option<Result<http::Request<Body>, ClientError>> {
+        let mut headers = self.headers.clone();
+        if is_cross_origin(from, to) {
+            for name in self.sensitive_headers.iter() {
+                headers.remove(name);
+            }
+        }
+        #[cfg(feature = "cookies")]
+        if let Some(jar) = &self.cookie_jar {
+            headers.remove(header::COOKIE);
+            if let Some(cookies) = jar.cookie_header(to) {
+                headers.insert(header::COOKIE, cookies);
+            }
+        }
+        if headers == self.headers {
+            return None;
+        }
+        self.headers = headers;
+
+        // Follow the same method changes as Cronet does for the redirect.
+        let drop_body = match status {
+            StatusCode::SEE_OTHER => self.method != Method::HEAD,
+            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => self.method == Method::POST,
+            _ => false,
+        };
+        let body = if drop_body {
+            self.method = Method::GET;
+            self.rewind = None;
+            for name in &CONTENT_HEADERS {
+                self.headers.remove(name);
+            }
+            Body::default()
+        } else {
+            match self.body() {
+                Ok(body) => body,
+                Err(error) => return Some(Err(ClientError::RedirectError(Box::new(error)))),
+            }
+        };
+
+        let mut request = http::Request::new(body);
+        *request.method_mut() = self.method.clone();
+        *request.headers_mut() = self.headers.clone();
+        match to.parse::<Uri>() {
+            Ok(uri) => *request.uri_mut() = uri,
+            Err(error) => return Some(Err(ClientError::RedirectError(Box::new(error)))),
+        }
+        Some(Ok(request))
+    }
+
+    /// Returns a new body with the original data.
+    fn body(&self) -> io::Result<Body> {
+        match &self.rewind {
+            Some(rewind) => Body::rewound(rewind),
+            None if self.method == Method::GET || self.method == Method::HEAD => {
+                Ok(Body::default())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "The request body can't be sent again",
+            )),
+        }
+    }
+}
+
+/// Returns whether both URLs have a different scheme, host or port.
+fn is_cross_origin(from: &str, to: &str) -> bool {
+    fn origin(url: &str) -> Option<(String, String, Option<u16>)> {
+        let uri = url.parse::<Uri>().ok()?;
+        let scheme = uri.scheme_str()?.to_ascii_lowercase();
+        let port = uri.port_u16().or(match scheme.as_str() {
+            "http" => Some(80),
+            "https" => Some(443),
+            _ => None,
+        });
+        Some((scheme, uri.host()?.to_ascii_lowercase(), port))
+    }
+
+    match (origin(from), origin(to)) {
+        (Some(from), Some(to)) => from != to,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, mem::ManuallyDrop, sync::Arc};
+
+    use http::{header, Method, StatusCode};
+
+    use crate::{
+        client::{Body, ClientCore},
+        Engine, Executor,
+    };
+
+    use super::{is_cross_origin, RequestTemplate, DEFAULT_SENSITIVE_HEADERS};
+
+    fn template(request: http::Request<Body>) -> RequestTemplate {
+        let core = Arc::new(ClientCore {
+            engine: ManuallyDrop::new(Engine::new()),
+            executor: ManuallyDrop::new(Executor::new(|_, runnable| runnable.run())),
+        });
+        let sensitive_headers = Arc::new(DEFAULT_SENSITIVE_HEADERS.to_vec());
+        RequestTemplate::new(core, request, sensitive_headers).0
+    }
+
+    #[test]
+    fn it_detects_cross_origin_urls() {
+        assert!(!is_cross_origin("https://a.com/x", "https://A.com:443/y"));
+        assert!(is_cross_origin("https://a.com", "https://b.com"));
+        assert!(is_cross_origin("http://a.com", "https://a.com"));
+        assert!(is_cross_origin("https://a.com", "https://a.com:8443"));
+    }
+
+    #[test]
+    fn it_strips_sensitive_headers_across_origins() {
+        let request = http::Request::post("https://a.com/login")
+            .header(header::AUTHORIZATION, "secret")
+            .header(header::ACCEPT, "*/*")
+            .body(Body::from("data"))
+            .unwrap();
+        let mut template = template(request);
+
+        assert!(template
+            .redirect(
+                StatusCode::TEMPORARY_REDIRECT,
+                "https://a.com/login",
+                "https://a.com/x"
+            )
+            .is_none());
+
+        let request = template
+            .redirect(
+                StatusCode::TEMPORARY_REDIRECT,
+                "https://a.com/login",
+                "https://b.com/x",
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(request.method(), Method::POST);
+        assert_eq!(request.uri(), "https://b.com/x");
+        assert!(!request.headers().contains_key(header::AUTHORIZATION));
+        assert!(request.headers().contains_key(header::ACCEPT));
+
+        let mut body = String::new();
+        request.into_body().read_to_string(&mut body).unwrap();
+        assert_eq!(body, "data");
+    }
+
+    #[test]
+    fn it_changes_the_method_on_see_other() {
+        let request = http::Request::post("https://a.com/form")
+            .header(header::COOKIE, "session=1")
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Body::from("data"))
+            .unwrap();
+        let mut template = template(request);
+
+        let request = template
+            .redirect(
+                StatusCode::SEE_OTHER,
+                "https://a.com/form",
+                "https://b.com/done",
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(request.method(), Method::GET);
+        assert!(!request.headers().contains_key(header::COOKIE));
+        assert!(!request.headers().contains_key(header::CONTENT_TYPE));
+        assert_eq!(request.body().as_bytes(), Some(&b""[..]));
+    }
+
+    #[cfg(feature = "cookies")]
+    #[test]
+    fn it_sends_cookies_of_the_jar_after_redirects() {
+        use crate::client::CookieJar;
+
+        let jar = Arc::new(CookieJar::new());
+        jar.add_cookie_str("a=1", "https://a.com/");
+
+        let mut request = http::Request::get("https://a.com/")
+            .body(Body::default())
+            .unwrap();
+        let mut template = template(
+            http::Request::get("https://a.com/")
+                .body(Body::default())
+                .unwrap(),
+        );
+        template.set_cookie_jar(jar.clone(), &mut request);
+        assert_eq!(request.headers()[header::COOKIE], "a=1");
+
+        // The jar has the same cookies for the new location.
+        assert!(template
+            .redirect(StatusCode::FOUND, "https://a.com/", "https://a.com/x")
+            .is_none());
+
+        // A cookie was set by the redirect.
+        jar.add_cookie_str("b=2; Path=/y", "https://a.com/");
+        let request = template
+            .redirect(StatusCode::FOUND, "https://a.com/x", "https://a.com/y")
+            .unwrap()
+            .unwrap();
+        let mut cookies: Vec<_> = request.headers()[header::COOKIE]
+            .to_str()
+            .unwrap()
+            .split("; ")
+            .collect();
+        cookies.sort();
+        assert_eq!(cookies, ["a=1", "b=2"]);
+
+        let request = template
+            .redirect(StatusCode::FOUND, "https://a.com/y", "https://b.com/")
+            .unwrap()
+            .unwrap();
+        assert!(!request.headers().contains_key(header::COOKIE));
+    }
+}
@@ -1,40 +1,92 @@
-use std::{mem, sync::mpsc::Sender};
+use std::{
+    io, mem,
+    sync::{
+        mpsc::{self, Sender},
+        Arc,
+    },
+};
 
-use bytes::BufMut;
-use http::Response;
+use bytes::{BufMut, Bytes};
+use http::{header::LOCATION, HeaderValue, Response, StatusCode};
 
 use crate::{
-    Buffer, CronetError, Destroy, UrlRequest, UrlRequestCallback, UrlRequestCallbackHandler,
-    UrlResponseInfo,
+    Borrowed, Buffer, CronetError, EngineResult, UrlRequest, UrlRequestCallback,
+    UrlRequestCallbackHandler, UrlRequestParams, UrlResponseInfo,
 };
 
-use super::Body;
-
-pub type ShouldRedirectFn = fn(new_location_url: &str) -> bool;
+#[cfg(feature = "cookies")]
+use super::CookieJar;
+use super::{
+    ActionKind, Body, BodyStream, CancelReason, Chunk, ClientError, ErrorInfo, RedirectPolicy,
+    RequestHandle, RequestTemplate, ResponseInfo, TimeoutPhase,
+};
 
 #[derive(Debug)]
 pub enum Status {
     Success(Response<Body>),
     Canceled,
-    Error(CronetError),
+    Error(ErrorInfo),
+    Failed(ClientError),
+}
+
+impl Status {
+    pub(crate) fn into_result(self) -> Result<Response<Body>, ClientError> {
+        match self {
+            Status::Success(res) => Ok(res),
+            Status::Canceled => Err(ClientError::CancellationError),
+            Status::Error(e) => Err(ClientError::CronetError(e)),
+            Status::Failed(e) => Err(e),
+        }
+    }
+}
+
+/// Receives the final [Status] of a request from a [ResponseHandler].
+pub trait StatusSink: Send {
+    fn complete(&mut self, status: Status);
+}
+
+impl StatusSink for Box<dyn StatusSink> {
+    fn complete(&mut self, status: Status) {
+        (**self).complete(status);
+    }
+}
+
+impl StatusSink for Sender<Status> {
+    fn complete(&mut self, status: Status) {
+        // The receiving end may already be gone, e.g. when the caller stopped waiting.
+        let _ = self.send(status);
+    }
 }
 
 pub struct ResponseHandler {
-    should_redirect: ShouldRedirectFn,
+    redirect_policy: Arc<RedirectPolicy>,
     response: Response<Body>,
-    tx: Sender<Status>,
-    buffer: Option<Buffer>,
+    sink: Box<dyn StatusSink>,
+    handle: Option<RequestHandle>,
     buffer_size: u64,
+    streaming: bool,
+    stream: Option<Sender<Chunk>>,
+    template: Option<RequestTemplate>,
+    /// URLs visited by earlier requests that were replaced after a redirect.
+    previous_urls: Vec<String>,
+    #[cfg(feature = "cookies")]
+    cookie_jar: Option<Arc<CookieJar>>,
 }
 
 impl ResponseHandler {
-    pub fn new(should_redirect: ShouldRedirectFn, tx: Sender<Status>) -> Self {
+    pub fn new(redirect_policy: Arc<RedirectPolicy>, sink: impl StatusSink + 'static) -> Self {
         Self {
-            should_redirect,
+            redirect_policy,
             response: Response::default(),
-            tx,
-            buffer: None,
+            sink: Box::new(sink),
+            handle: None,
             buffer_size: 512,
+            streaming: false,
+            stream: None,
+            template: None,
+            previous_urls: Vec::new(),
+            #[cfg(feature = "cookies")]
+            cookie_jar: None,
         }
     }
 
@@ -44,52 +96,252 @@ impl ResponseHandler {
         self.buffer_size = buffer_size;
     }
 
-    /// Continues reading the response body.
-    fn read(&mut self, req: UrlRequest) {
-        if let Some(old_buffer) = &self.buffer {
-            old_buffer.destroy();
+    /// Enables streaming of the response body.
+    ///
+    /// When enabled, the response is reported as soon as its headers are received.
+    /// Its body is a [BodyStream] that reads the remaining data on demand.
+    /// This requires a request handle (see `set_request_handle`).
+    pub fn set_streaming(&mut self, streaming: bool) {
+        self.streaming = streaming;
+    }
+
+    /// Sets the handle that owns the request, which is released once the request finishes.
+    pub(crate) fn set_request_handle(&mut self, handle: RequestHandle) {
+        self.handle = Some(handle);
+    }
+
+    /// Sets the template that is used to send the request again after a cross-origin redirect.
+    /// Requires a request handle (see `set_request_handle`).
+    pub(crate) fn set_request_template(&mut self, template: RequestTemplate) {
+        self.template = Some(template);
+    }
+
+    /// Sets the jar that stores the cookies of every response, including redirects.
+    #[cfg(feature = "cookies")]
+    pub(crate) fn set_cookie_jar(&mut self, jar: Arc<CookieJar>) {
+        self.cookie_jar = Some(jar);
+    }
+
+    /// Stores the cookies set by the response in the cookie jar.
+    #[cfg(feature = "cookies")]
+    fn store_cookies(&self, info: &UrlResponseInfo) {
+        if let Some(jar) = &self.cookie_jar {
+            let values = (0..info.header_size())
+                .map(|i| info.header_at(i))
+                .filter(|header| header.name().eq_ignore_ascii_case("set-cookie"))
+                .map(|header| header.value().into_owned());
+            jar.store_response_cookies(&info.url(), values);
         }
+    }
 
-        let buffer = Buffer::new_with_size(self.buffer_size);
-        self.buffer = Some(Buffer { ptr: buffer.ptr });
+    /// Sets the response from `info`, including the URLs of earlier requests in its [ResponseInfo].
+    fn set_response(&mut self, info: &UrlResponseInfo) -> Result<(), ClientError> {
+        self.response = Response::try_from(info)?;
+        if let Some(info) = self.response.extensions_mut().get_mut::<ResponseInfo>() {
+            info.url_chain
+                .splice(0..0, self.previous_urls.iter().cloned());
+        }
+        Ok(())
+    }
 
-        req.read(buffer);
+    /// Cancels the request, which then fails with the given error.
+    fn fail(&self, req: &UrlRequest, error: ClientError) {
+        match &self.handle {
+            Some(handle) => handle.cancel_with(CancelReason::Failed(error)),
+            None => req.cancel(),
+        }
+    }
+
+    /// Reads the next part of the response body into `buffer`.
+    /// Fails the request if Cronet refuses to read.
+    fn read(&mut self, req: &UrlRequest, buffer: Buffer) {
+        let result = req.read(buffer);
+        if result != EngineResult::Success {
+            self.fail(req, ClientError::EngineError(result));
+        }
+    }
+
+    /// Releases the finished request and reports its final status.
+    /// If the response is being streamed, the status ends the stream instead.
+    fn finish(&mut self, status: Status) {
+        if let Some(handle) = &self.handle {
+            handle.release();
+        }
+
+        match self.stream.take() {
+            Some(stream) => {
+                let chunk = match status {
+                    Status::Success(_) => Chunk::End,
+                    Status::Canceled => Chunk::Canceled,
+                    Status::Error(error) => Chunk::Error(io::Error::other(error.to_string())),
+                    Status::Failed(error @ ClientError::Timeout { .. }) => {
+                        Chunk::Error(io::Error::new(io::ErrorKind::TimedOut, error.to_string()))
+                    }
+                    Status::Failed(error) => Chunk::Error(io::Error::other(error.to_string())),
+                };
+                // The stream may have been dropped already.
+                let _ = stream.send(chunk);
+            }
+            None => self.sink.complete(status),
+        }
+    }
+
+    /// Replaces the cancelled request with a new request that uses the same callback.
+    fn reissue(&mut self, callback: &UrlRequestCallback, request: http::Request<Body>) {
+        let (template, handle) = match (&self.template, &self.handle) {
+            (Some(template), Some(handle)) => (template, handle.clone()),
+            _ => return self.finish(Status::Canceled),
+        };
+
+        let uri = request.uri().to_string();
+        let request_parameters = UrlRequestParams::from(request);
+        request_parameters.set_upload_data_executor(&template.core.executor);
+        request_parameters.set_allow_direct_executor(template.allow_direct_executor);
+
+        let url_request = UrlRequest::new();
+        let result = url_request.init_with_params(
+            &template.core.engine,
+            uri.as_str(),
+            &request_parameters,
+            callback,
+            &template.core.executor,
+        );
+        if result != EngineResult::Success {
+            return self.finish(Status::Failed(ClientError::EngineError(result)));
+        }
+        if !handle.replace(url_request) {
+            return self.finish(Status::Canceled);
+        }
+
+        let result = handle.start();
+        if result != EngineResult::Success {
+            self.finish(Status::Failed(ClientError::EngineError(result)));
+        }
     }
 }
 
 impl UrlRequestCallbackHandler for ResponseHandler {
     fn on_redirect_received(
         &mut self,
-        _: UrlRequestCallback,
-        request: UrlRequest,
-        info: UrlResponseInfo,
+        _: Borrowed<'_, UrlRequestCallback>,
+        request: Borrowed<'_, UrlRequest>,
+        info: Borrowed<'_, UrlResponseInfo>,
         new_location_url: &str,
     ) {
-        if (self.should_redirect)(new_location_url) {
-            request.follow_redirect();
-        } else {
-            self.response = info.into();
+        if let Some(handle) = &self.handle {
+            handle.touch();
+        }
+        #[cfg(feature = "cookies")]
+        self.store_cookies(&info);
+        let mut previous = self.previous_urls.clone();
+        previous.extend((0..info.url_chain_size()).map(|i| info.url_chain_at(i).to_string()));
+        // Cronet only reports redirects for 3xx responses, which are always valid status codes.
+        let status = StatusCode::from_u16(info.status_code() as u16).unwrap_or_default();
+        let action = self
+            .redirect_policy
+            .redirect(status, new_location_url, &previous);
+
+        let reason = match action.kind {
+            ActionKind::Follow => {
+                let redirect = self
+                    .template
+                    .as_mut()
+                    .and_then(|template| template.redirect(status, &info.url(), new_location_url));
+                match redirect {
+                    None => {
+                        let result = request.follow_redirect();
+                        if result != EngineResult::Success {
+                            self.fail(&request, ClientError::EngineError(result));
+                        }
+                        return;
+                    }
+                    // Send the request again without sensitive headers, after this one is cancelled.
+                    Some(Ok(redirected)) => {
+                        self.previous_urls = previous;
+                        CancelReason::Reissue(Box::new(redirected))
+                    }
+                    Some(Err(error)) => CancelReason::Failed(error),
+                }
+            }
+            ActionKind::Stop => {
+                // Stop at the redirect and return its response once the request is cancelled.
+                if let Err(error) = self.set_response(&info) {
+                    return self.fail(&request, error);
+                }
+                if !self.response.headers().contains_key(LOCATION) {
+                    if let Ok(location) = HeaderValue::from_str(new_location_url) {
+                        self.response.headers_mut().insert(LOCATION, location);
+                    }
+                }
+                CancelReason::Redirect
+            }
+            ActionKind::Error(error) => CancelReason::Failed(ClientError::RedirectError(error)),
+            ActionKind::TooManyRedirects(max) => {
+                CancelReason::Failed(ClientError::TooManyRedirects(max))
+            }
+            ActionKind::RedirectLoop(url) => CancelReason::Failed(ClientError::RedirectLoop(url)),
+        };
+
+        match &self.handle {
+            Some(handle) => handle.cancel_with(reason),
+            None => request.cancel(),
         }
     }
 
     fn on_response_started(
         &mut self,
-        _: UrlRequestCallback,
-        req: UrlRequest,
-        info: UrlResponseInfo,
+        _: Borrowed<'_, UrlRequestCallback>,
+        req: Borrowed<'_, UrlRequest>,
+        info: Borrowed<'_, UrlResponseInfo>,
     ) {
-        self.response = info.into();
-        self.read(req);
+        #[cfg(feature = "cookies")]
+        self.store_cookies(&info);
+        if let Err(error) = self.set_response(&info) {
+            return self.fail(&req, error);
+        }
+        if let Some(handle) = &self.handle {
+            handle.set_phase(TimeoutPhase::Body);
+            handle.touch();
+        }
+
+        match &self.handle {
+            Some(handle) if self.streaming => {
+                handle.pause();
+                let (tx, rx) = mpsc::channel();
+                self.stream = Some(tx);
+                let stream = BodyStream::new(handle.clone(), rx, self.buffer_size);
+                *self.response.body_mut() = Body::new(stream);
+                let response = mem::take(&mut self.response);
+                self.sink.complete(Status::Success(response));
+            }
+            _ => self.read(&req, Buffer::new_with_size(self.buffer_size)),
+        }
     }
 
     fn on_read_completed(
         &mut self,
-        _: UrlRequestCallback,
-        req: UrlRequest,
-        _: UrlResponseInfo,
+        _: Borrowed<'_, UrlRequestCallback>,
+        req: Borrowed<'_, UrlRequest>,
+        _: Borrowed<'_, UrlResponseInfo>,
         buffer: Buffer,
         bytes_read: u64,
     ) {
+        if let Some(handle) = &self.handle {
+            match self.stream {
+                Some(_) => handle.pause(),
+                None => handle.touch(),
+            }
+        }
+
+        if let Some(stream) = &self.stream {
+            // The end of the body is reported by `on_succeeded`.
+            if bytes_read > 0 {
+                let data = Bytes::copy_from_slice(buffer.data_slice::<u8>(bytes_read as usize));
+                let _ = stream.send(Chunk::Data(data));
+            }
+            return;
+        }
+
         if bytes_read == 0 {
             return;
         }
@@ -97,28 +349,65 @@ impl UrlRequestCallbackHandler for ResponseHandler {
         let data = buffer.data_slice::<u8>(bytes_read as usize);
         self.response.body_mut().as_bytes_mut().unwrap().put(data);
 
-        self.read(req);
+        // The buffer is handed back by Cronet, so it can be reused for the next read.
+        self.read(&req, buffer);
     }
 
-    fn on_succeeded(&mut self, _: UrlRequestCallback, req: UrlRequest, _: UrlResponseInfo) {
-        req.destroy();
+    fn on_succeeded(
+        &mut self,
+        _: Borrowed<'_, UrlRequestCallback>,
+        _: Borrowed<'_, UrlRequest>,
+        info: Borrowed<'_, UrlResponseInfo>,
+    ) {
+        if let Some(response_info) = self.response.extensions_mut().get_mut::<ResponseInfo>() {
+            response_info.received_byte_count = info.received_byte_count();
+        }
         let response = mem::take(&mut self.response);
-        self.tx.send(Status::Success(response)).unwrap();
+        self.finish(Status::Success(response));
     }
 
     fn on_failed(
         &mut self,
-        _: UrlRequestCallback,
-        req: UrlRequest,
-        _: UrlResponseInfo,
-        error: CronetError,
+        _: Borrowed<'_, UrlRequestCallback>,
+        _: Borrowed<'_, UrlRequest>,
+        _: Option<Borrowed<'_, UrlResponseInfo>>,
+        error: Borrowed<'_, CronetError>,
     ) {
-        req.destroy();
-        self.tx.send(Status::Error(error)).unwrap();
+        // The error is destroyed along with the request, so a copy is reported.
+        self.finish(Status::Error(ErrorInfo::from(&*error)));
     }
 
-    fn on_canceled(&mut self, _: UrlRequestCallback, req: UrlRequest, _: UrlResponseInfo) {
-        req.destroy();
-        self.tx.send(Status::Canceled).unwrap();
+    fn on_canceled(
+        &mut self,
+        callback: Borrowed<'_, UrlRequestCallback>,
+        _: Borrowed<'_, UrlRequest>,
+        _: Option<Borrowed<'_, UrlResponseInfo>>,
+    ) {
+        let reason = self.handle.as_ref().and_then(|h| h.take_cancel_reason());
+        let status = match reason {
+            Some(CancelReason::Reissue(request)) => return self.reissue(&callback, *request),
+            Some(CancelReason::Timeout(phase, status)) => {
+                Status::Failed(ClientError::Timeout { phase, status })
+            }
+            Some(CancelReason::Redirect) => Status::Success(mem::take(&mut self.response)),
+            Some(CancelReason::Failed(error)) => Status::Failed(error),
+            None => Status::Canceled,
+        };
+        self.finish(status);
+    }
+
+    fn on_panicked(
+        &mut self,
+        _: Borrowed<'_, UrlRequestCallback>,
+        req: Borrowed<'_, UrlRequest>,
+        message: String,
+    ) {
+        let error = ClientError::HandlerPanicked(message);
+        // A request that is done won't be cancelled, so its status is reported right away.
+        if req.is_done() {
+            self.finish(Status::Failed(error));
+        } else {
+            self.fail(&req, error);
+        }
     }
 }
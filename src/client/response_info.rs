@@ -0,0 +1,95 @@
+use crate::UrlResponseInfo;
+
+/// Metadata of a response, attached to each [http::Response] as an extension.
+///
+/// ```no_run
+/// use cronet_rs::client::{Body, Client, ResponseInfo};
+///
+/// let client = Client::new();
+/// let request = http::Request::get("https://example.com").body(Body::default()).unwrap();
+/// let response = client.send(request).unwrap();
+/// let info = response.extensions().get::<ResponseInfo>().unwrap();
+/// println!("final URL: {}, cached: {}", info.url, info.cached);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResponseInfo {
+    /// The URL the response is for, i.e. the final URL after following redirects.
+    pub url: String,
+
+    /// The URLs of all requests, starting with the original URL and ending with `url`.
+    pub url_chain: Vec<String>,
+
+    /// Whether the response came from the cache, including responses that were revalidated over the network.
+    pub cached: bool,
+
+    /// The proxy server that was used for the request.
+    pub proxy_server: String,
+
+    /// The amount of bytes received from the network, including headers and data from all redirects.
+    /// For responses with a buffered body, this includes the whole body.
+    pub received_byte_count: i64,
+
+    /// The protocol negotiated with the server as reported by Cronet (for example `h2` or `quic/1+spdy/3`).
+    /// Empty if no protocol was negotiated.
+    pub negotiated_protocol: String,
+
+    /// The HTTP status text of the status line (for example `OK`).
+    pub status_text: String,
+}
+
+impl From<&UrlResponseInfo> for ResponseInfo {
+    fn from(info: &UrlResponseInfo) -> Self {
+        Self {
+            url: info.url().to_string(),
+            url_chain: (0..info.url_chain_size())
+                .map(|i| info.url_chain_at(i).to_string())
+                .collect(),
+            cached: info.cached(),
+            proxy_server: info.proxy_server().to_string(),
+            received_byte_count: info.received_byte_count(),
+            negotiated_protocol: info.negotiated_protocol().to_string(),
+            status_text: info.status_text().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::UrlResponseInfo;
+
+    use super::ResponseInfo;
+
+    #[test]
+    fn it_copies_url_response_info() {
+        let url_response_info = UrlResponseInfo::new();
+        url_response_info.set_url("https://example.com/b");
+        url_response_info.add_url_chain("https://example.com/a");
+        url_response_info.add_url_chain("https://example.com/b");
+        url_response_info.set_cached(true);
+        url_response_info.set_status_text("OK");
+        url_response_info.set_negotiated_protocol("h2");
+        url_response_info.set_received_byte_count(42);
+
+        let info = ResponseInfo::from(&url_response_info);
+        assert_eq!(info.url, "https://example.com/b");
+        assert_eq!(
+            info.url_chain,
+            vec!["https://example.com/a", "https://example.com/b"]
+        );
+        assert!(info.cached);
+        assert_eq!(info.status_text, "OK");
+        assert_eq!(info.negotiated_protocol, "h2");
+        assert_eq!(info.received_byte_count, 42);
+    }
+
+    #[test]
+    fn it_is_attached_to_responses() {
+        let url_response_info = UrlResponseInfo::new();
+        url_response_info.set_url("https://example.com");
+        url_response_info.set_status_code(200);
+
+        let response = http::Response::<()>::try_from(&url_response_info).unwrap();
+        let info = response.extensions().get::<ResponseInfo>().unwrap();
+        assert_eq!(info.url, "https://example.com");
+    }
+}
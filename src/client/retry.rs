@@ -0,0 +1,423 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use http::Method;
+
+use crate::{ErrorCode, UrlRequest};
+
+use super::{timeout, Body, Client, ErrorInfo, RequestHandle, SharedRewind, Status, StatusSink};
+
+/// Error codes that are retried by default, even if Cronet doesn't report them as retryable.
+pub const DEFAULT_RETRYABLE_ERROR_CODES: [ErrorCode; 2] =
+    [ErrorCode::NetworkChanged, ErrorCode::ConnectionReset];
+
+/// Determines whether a [super::Client] retries failed requests.
+///
+/// A request is only retried if it is idempotent (see [Idempotency]), its body can be rewound
+/// and it failed with an error that Cronet reports as retryable (see [CronetError::retryable])
+/// or with one of the retryable error codes.
+/// The failed attempts are attached to the final response or Cronet error as a [RetryHistory]
+/// (see [super::ClientError::retry_history]). Other errors, such as cancellations and timeouts, are returned as is.
+/// Timeouts (see [super::Timeout]) apply to each attempt separately.
+///
+/// The default policy doesn't retry requests.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use cronet_rs::client::{Client, RetryPolicy};
+///
+/// let client = Client::builder()
+///     .retry_policy(RetryPolicy::new(3).backoff(Duration::from_millis(100), Duration::from_secs(2)))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    error_codes: Vec<ErrorCode>,
+}
+
+impl RetryPolicy {
+    /// Makes up to `max_attempts` attempts per request, including the first one.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            error_codes: DEFAULT_RETRYABLE_ERROR_CODES.to_vec(),
+        }
+    }
+
+    /// Doesn't retry requests.
+    pub fn none() -> Self {
+        Self::new(1)
+    }
+
+    /// Sets the delay before the first retry, which doubles with each retry up to `max`.
+    /// A random jitter of up to half the delay is subtracted from each delay.
+    /// The default is 100 milliseconds, up to 10 seconds.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Sets the error codes that are retried even if Cronet doesn't report the error as retryable.
+    /// The default is [DEFAULT_RETRYABLE_ERROR_CODES].
+    pub fn retry_on(mut self, error_codes: impl IntoIterator<Item = ErrorCode>) -> Self {
+        self.error_codes = error_codes.into_iter().collect();
+        self
+    }
+
+    /// Returns whether requests may be retried at all.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.max_attempts > 1
+    }
+
+    /// Returns whether a request that failed with `error` after `attempts` attempts is retried.
+    fn should_retry(&self, attempts: usize, error: &ErrorInfo) -> bool {
+        attempts < self.max_attempts as usize
+            && (error.retryable || self.error_codes.contains(&error.error_code))
+    }
+
+    /// Returns the delay before the given retry, starting at `0`.
+    fn backoff_for(&self, retry: usize) -> Duration {
+        let factor = 2u32.saturating_pow(retry as u32);
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff);
+        backoff.mul_f64(1.0 - random() / 2.0)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+/// Request extension that marks whether a request can be retried safely,
+/// overriding the default that is based on its method.
+///
+/// `GET`, `HEAD`, `OPTIONS`, `TRACE`, `PUT` and `DELETE` requests are idempotent by default.
+///
+/// ```no_run
+/// use cronet_rs::client::{Body, Idempotency};
+///
+/// let mut request = http::Request::post("https://example.com").body(Body::from("data")).unwrap();
+/// request.extensions_mut().insert(Idempotency::Idempotent);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Idempotency {
+    Idempotent,
+    NotIdempotent,
+}
+
+/// A failed attempt of a request that was retried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryAttempt {
+    pub error_code: ErrorCode,
+    pub internal_error_code: i32,
+    pub quic_detailed_error_code: i32,
+    pub message: String,
+    /// How long the client waited before the next attempt.
+    pub backoff: Duration,
+}
+
+impl RetryAttempt {
+    fn new(error: &ErrorInfo, backoff: Duration) -> Self {
+        Self {
+            error_code: error.error_code,
+            internal_error_code: error.internal_error_code,
+            quic_detailed_error_code: error.quic_detailed_error_code,
+            message: error.message.clone(),
+            backoff,
+        }
+    }
+}
+
+/// The failed attempts of a retried request, in order.
+///
+/// Attached to the response as an extension, or to the error as [super::ErrorInfo::retry_history].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetryHistory {
+    pub attempts: Vec<RetryAttempt>,
+}
+
+/// Returns whether the request can be retried safely.
+fn is_idempotent<T>(request: &http::Request<T>) -> bool {
+    match request.extensions().get::<Idempotency>() {
+        Some(idempotency) => *idempotency == Idempotency::Idempotent,
+        None => matches!(
+            *request.method(),
+            Method::GET
+                | Method::HEAD
+                | Method::OPTIONS
+                | Method::TRACE
+                | Method::PUT
+                | Method::DELETE
+        ),
+    }
+}
+
+/// Returns a random number in `[0, 1)`.
+fn random() -> f64 {
+    // Each `RandomState` is seeded with different keys.
+    let hash = RandomState::new().build_hasher().finish();
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Attaches the retry history to the final status of a request.
+fn attach_history(status: Status, history: RetryHistory) -> Status {
+    if history.attempts.is_empty() {
+        return status;
+    }
+    match status {
+        Status::Success(mut response) => {
+            response.extensions_mut().insert(history);
+            Status::Success(response)
+        }
+        Status::Error(mut error) => {
+            error.retry_history = history;
+            Status::Error(error)
+        }
+        status => status,
+    }
+}
+
+/// Wraps `sink` to retry the request according to the retry policy of the client.
+/// Returns the request unchanged if it can't be retried.
+pub(crate) fn wrap(
+    client: &Client,
+    handle: &RequestHandle,
+    request: http::Request<Body>,
+    sink: impl StatusSink + 'static,
+    streaming: bool,
+) -> (http::Request<Body>, Box<dyn StatusSink>) {
+    if !client.retry_policy.is_enabled() || !is_idempotent(&request) {
+        return (request, Box::new(sink));
+    }
+
+    let (parts, mut body) = request.into_parts();
+    let Some(rewind) = body.share_rewind() else {
+        return (http::Request::from_parts(parts, body), Box::new(sink));
+    };
+
+    let state = RetryState {
+        client: client.clone(),
+        handle: handle.clone(),
+        parts: parts.clone(),
+        rewind,
+        streaming,
+        history: RetryHistory::default(),
+        sink: Box::new(sink),
+    };
+    let sink = RetrySink {
+        state: Arc::new(Mutex::new(Some(state))),
+    };
+    (http::Request::from_parts(parts, body), Box::new(sink))
+}
+
+struct RetryState {
+    client: Client,
+    handle: RequestHandle,
+    parts: http::request::Parts,
+    rewind: SharedRewind,
+    streaming: bool,
+    history: RetryHistory,
+    sink: Box<dyn StatusSink>,
+}
+
+impl RetryState {
+    fn finish(mut self, status: Status) {
+        self.sink.complete(attach_history(status, self.history));
+    }
+}
+
+/// Sends the request again after a retryable failure, or reports the final status to the wrapped sink.
+struct RetrySink {
+    state: Arc<Mutex<Option<RetryState>>>,
+}
+
+impl RetrySink {
+    /// Sends the request again with the given body, using the same [RequestHandle].
+    fn restart(state: Arc<Mutex<Option<RetryState>>>, body: Body) {
+        let (client, handle, request, streaming) = match state.lock().unwrap().as_ref() {
+            Some(state) => (
+                state.client.clone(),
+                state.handle.clone(),
+                http::Request::from_parts(state.parts.clone(), body),
+                state.streaming,
+            ),
+            None => return,
+        };
+
+        if !handle.replace(UrlRequest::new()) {
+            if let Some(state) = state.lock().unwrap().take() {
+                state.finish(Status::Canceled);
+            }
+            return;
+        }
+
+        let sink = RetrySink {
+            state: state.clone(),
+        };
+        if let Err(error) = client.run(&handle, request, sink, streaming) {
+            if let Some(state) = state.lock().unwrap().take() {
+                state.finish(Status::Failed(error));
+            }
+        }
+    }
+}
+
+impl StatusSink for RetrySink {
+    fn complete(&mut self, status: Status) {
+        let mut guard = self.state.lock().unwrap();
+        let Some(state) = guard.as_mut() else {
+            return;
+        };
+
+        let attempts = state.history.attempts.len() + 1;
+        let body = match &status {
+            Status::Error(error) if state.client.retry_policy.should_retry(attempts, error) => {
+                Body::rewound(&state.rewind).ok()
+            }
+            _ => None,
+        };
+
+        match (body, status) {
+            (Some(body), Status::Error(error)) => {
+                let backoff = state.client.retry_policy.backoff_for(attempts - 1);
+                state
+                    .history
+                    .attempts
+                    .push(RetryAttempt::new(&error, backoff));
+                drop(guard);
+
+                let state = self.state.clone();
+                timeout::schedule(Instant::now() + backoff, move || {
+                    RetrySink::restart(state, body);
+                });
+            }
+            (_, status) => {
+                if let Some(state) = guard.take() {
+                    drop(guard);
+                    state.finish(status);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::Method;
+
+    use crate::{
+        client::{Body, ClientError, ErrorInfo, Status},
+        CronetError, ErrorCode,
+    };
+
+    use super::{
+        attach_history, is_idempotent, Idempotency, RetryAttempt, RetryHistory, RetryPolicy,
+    };
+
+    fn history() -> RetryHistory {
+        RetryHistory {
+            attempts: vec![RetryAttempt {
+                error_code: ErrorCode::NetworkChanged,
+                internal_error_code: -21,
+                quic_detailed_error_code: 0,
+                message: String::from("net::ERR_NETWORK_CHANGED"),
+                backoff: Duration::from_millis(100),
+            }],
+        }
+    }
+
+    #[test]
+    fn it_only_retries_idempotent_requests() {
+        let request = |method| {
+            http::Request::builder()
+                .method(method)
+                .body(Body::default())
+                .unwrap()
+        };
+        assert!(is_idempotent(&request(Method::GET)));
+        assert!(is_idempotent(&request(Method::PUT)));
+        assert!(!is_idempotent(&request(Method::POST)));
+
+        let mut post = request(Method::POST);
+        post.extensions_mut().insert(Idempotency::Idempotent);
+        assert!(is_idempotent(&post));
+
+        let mut get = request(Method::GET);
+        get.extensions_mut().insert(Idempotency::NotIdempotent);
+        assert!(!is_idempotent(&get));
+    }
+
+    #[test]
+    fn it_retries_retryable_errors() {
+        let policy = RetryPolicy::new(3);
+        let error = CronetError::new();
+        error.set_error_code(ErrorCode::HostnameNotResolved);
+        let mut error = ErrorInfo::from(&error);
+        assert!(!policy.should_retry(1, &error));
+
+        error.retryable = true;
+        assert!(policy.should_retry(2, &error));
+        assert!(!policy.should_retry(3, &error));
+
+        error.retryable = false;
+        error.error_code = ErrorCode::ConnectionReset;
+        assert!(policy.should_retry(1, &error));
+        assert!(!RetryPolicy::none().should_retry(1, &error));
+    }
+
+    #[test]
+    fn it_backs_off_exponentially() {
+        let policy =
+            RetryPolicy::new(5).backoff(Duration::from_millis(100), Duration::from_millis(300));
+        for (retry, max) in [(0, 100), (1, 200), (2, 300), (10, 300)] {
+            let backoff = policy.backoff_for(retry);
+            let max = Duration::from_millis(max);
+            assert!(backoff <= max && backoff >= max / 2, "{:?}", backoff);
+        }
+    }
+
+    #[test]
+    fn it_attaches_the_history() {
+        let status = attach_history(Status::Success(http::Response::default()), history());
+        match status {
+            Status::Success(response) => {
+                assert_eq!(
+                    response.extensions().get::<RetryHistory>(),
+                    Some(&history())
+                )
+            }
+            other => panic!("unexpected status: {:?}", other),
+        }
+
+        let error = ErrorInfo::from(&CronetError::new());
+        let error = attach_history(Status::Error(error), history()).into_result();
+        assert_eq!(error.unwrap_err().retry_history(), Some(&history()));
+
+        assert!(matches!(
+            attach_history(Status::Canceled, history()).into_result(),
+            Err(ClientError::CancellationError)
+        ));
+
+        assert!(matches!(
+            attach_history(Status::Canceled, RetryHistory::default()),
+            Status::Canceled
+        ));
+    }
+}
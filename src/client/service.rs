@@ -0,0 +1,190 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use tower_service::Service;
+
+use super::{AsyncClient, Body, ClientError, ResponseFuture};
+
+/// Default maximum number of requests an [AsyncClient] sends concurrently through [Service].
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 100;
+
+/// Sends requests through the [AsyncClient], so it can be wrapped by tower middleware.
+///
+/// `poll_ready` is pending while the maximum number of requests is in flight
+/// (see [AsyncClient::max_in_flight]).
+/// If `call` is invoked without a slot reserved by `poll_ready`, the request waits for a slot before it is sent.
+impl Service<http::Request<Body>> for AsyncClient {
+    type Response = http::Response<Body>;
+    type Error = ClientError;
+    type Future = ServiceFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.permit.is_none() {
+            match self.limit.poll_acquire(cx) {
+                Poll::Ready(permit) => self.permit = Some(permit),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        match self.permit.take() {
+            Some(permit) => ServiceFuture {
+                state: State::Sending(self.send(request)),
+                permit: Some(permit),
+            },
+            None => ServiceFuture {
+                state: State::Waiting(self.clone(), Some(Box::new(request))),
+                permit: None,
+            },
+        }
+    }
+}
+
+/// Future returned by [AsyncClient] as a [Service].
+///
+/// Dropping the future before it completes cancels the request.
+pub struct ServiceFuture {
+    state: State,
+    permit: Option<Permit>,
+}
+
+enum State {
+    /// Waiting for a slot, as `poll_ready` wasn't called before the request.
+    Waiting(AsyncClient, Option<Box<http::Request<Body>>>),
+    Sending(ResponseFuture),
+}
+
+impl Future for ServiceFuture {
+    type Output = Result<http::Response<Body>, ClientError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if let State::Waiting(client, request) = &mut this.state {
+            match client.limit.poll_acquire(cx) {
+                Poll::Ready(permit) => this.permit = Some(permit),
+                Poll::Pending => return Poll::Pending,
+            }
+            let request = request.take().expect("request was already sent");
+            this.state = State::Sending(client.send(*request));
+        }
+
+        let State::Sending(future) = &mut this.state else {
+            unreachable!("the request is sent once a slot is reserved");
+        };
+        let result = Pin::new(future).poll(cx);
+        if result.is_ready() {
+            // The request is no longer in flight.
+            this.permit = None;
+        }
+        result
+    }
+}
+
+/// Limits the number of requests in flight.
+pub(crate) struct Limit {
+    max: usize,
+    state: Mutex<LimitState>,
+}
+
+struct LimitState {
+    in_flight: usize,
+    waiters: Vec<Waker>,
+}
+
+impl Limit {
+    pub(crate) fn new(max: usize) -> Arc<Self> {
+        Arc::new(Self {
+            max,
+            state: Mutex::new(LimitState {
+                in_flight: 0,
+                waiters: Vec::new(),
+            }),
+        })
+    }
+
+    /// Reserves a slot for a request, or registers the task to be woken once one is free.
+    fn poll_acquire(self: &Arc<Self>, cx: &mut Context<'_>) -> Poll<Permit> {
+        let mut state = self.state.lock().unwrap();
+        if state.in_flight < self.max {
+            state.in_flight += 1;
+            return Poll::Ready(Permit {
+                limit: self.clone(),
+            });
+        }
+        if !state.waiters.iter().any(|w| w.will_wake(cx.waker())) {
+            state.waiters.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// A reserved slot of a [Limit], which is freed when dropped.
+pub(crate) struct Permit {
+    limit: Arc<Limit>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let waiters = {
+            let mut state = self.limit.state.lock().unwrap();
+            state.in_flight -= 1;
+            std::mem::take(&mut state.waiters)
+        };
+        for waiter in waiters {
+            waiter.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        task::{Context, Wake, Waker},
+    };
+
+    use crate::client::AsyncClient;
+
+    use super::{Limit, ServiceFuture};
+
+    struct FlagWaker(AtomicBool);
+
+    impl Wake for FlagWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn it_limits_requests_in_flight() {
+        let limit = Limit::new(1);
+        let flag = Arc::new(FlagWaker(AtomicBool::new(false)));
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let permit = limit.poll_acquire(&mut cx);
+        assert!(permit.is_ready());
+        assert!(limit.poll_acquire(&mut cx).is_pending());
+
+        drop(permit);
+        assert!(flag.0.load(Ordering::SeqCst));
+        assert!(limit.poll_acquire(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn it_can_be_shared_by_middleware() {
+        fn assert_send_clone<T: Send + Clone + 'static>() {}
+        fn assert_send<T: Send + 'static>() {}
+        assert_send_clone::<AsyncClient>();
+        assert_send::<ServiceFuture>();
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    sync::{Condvar, Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{unwind, UrlRequestStatusListener};
+
+use super::{CancelReason, RequestHandle, TimeoutCheck};
+
+/// Request extension that limits the total duration of a request,
+/// overriding the timeout of the client.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use cronet_rs::client::{Body, Timeout};
+///
+/// let mut request = http::Request::get("https://example.com").body(Body::default()).unwrap();
+/// request.extensions_mut().insert(Timeout(Duration::from_secs(10)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timeout(pub Duration);
+
+/// Request extension that limits how long a request may wait on the network without making progress,
+/// overriding the read timeout of the client.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReadTimeout(pub Duration);
+
+/// Phase of a request when it timed out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeoutPhase {
+    /// Waiting for the response headers.
+    Headers,
+
+    /// Reading the response body.
+    Body,
+}
+
+/// How long to wait for the status of a timed out request before cancelling it without one.
+const STATUS_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Watches the request on the shared timer thread and cancels it once one of the timeouts expires.
+pub(crate) fn watch(
+    handle: RequestHandle,
+    timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+) {
+    if timeout.is_none() && read_timeout.is_none() {
+        return;
+    }
+
+    let now = Instant::now();
+    let deadline = timeout.map(|timeout| now + timeout);
+    let task = Task::Watch {
+        handle,
+        deadline,
+        read_timeout,
+    };
+    Timer::shared().schedule(Entry { at: now, task });
+}
+
+/// Runs `f` on the shared timer thread once `at` is reached.
+pub(crate) fn schedule(at: Instant, f: impl FnOnce() + Send + 'static) {
+    let task = Task::Run(Box::new(f));
+    Timer::shared().schedule(Entry { at, task });
+}
+
+/// Work of the [Timer] that is due at a certain time.
+enum Task {
+    /// Checks the timeouts of a request.
+    Watch {
+        handle: RequestHandle,
+        deadline: Option<Instant>,
+        read_timeout: Option<Duration>,
+    },
+    /// Cancels a timed out request whose status didn't arrive in time.
+    Cancel {
+        handle: RequestHandle,
+        phase: TimeoutPhase,
+    },
+    /// Runs a function that was scheduled with [schedule].
+    Run(Box<dyn FnOnce() + Send>),
+}
+
+impl Task {
+    /// Runs the task and returns the next one, if any.
+    fn run(self, now: Instant) -> Option<Entry> {
+        match self {
+            Task::Watch {
+                ref handle,
+                deadline,
+                read_timeout,
+            } => match handle.check_timeout(deadline, read_timeout, now) {
+                TimeoutCheck::Finished | TimeoutCheck::Pending(None) => None,
+                TimeoutCheck::Pending(Some(at)) => Some(Entry { at, task: self }),
+                TimeoutCheck::Expired(phase) => {
+                    // The request is cancelled once its status arrives, or without a status after a while.
+                    let status_handle = handle.clone();
+                    let listener = UrlRequestStatusListener::new(move |_, status| {
+                        status_handle.cancel_with(CancelReason::Timeout(phase, Some(status)));
+                    });
+                    let handle = handle.clone();
+                    if !handle.status(listener) {
+                        return None;
+                    }
+                    Some(Entry {
+                        at: now + STATUS_TIMEOUT,
+                        task: Task::Cancel { handle, phase },
+                    })
+                }
+            },
+            Task::Cancel { handle, phase } => {
+                handle.cancel_with(CancelReason::Timeout(phase, None));
+                None
+            }
+            Task::Run(f) => {
+                f();
+                None
+            }
+        }
+    }
+}
+
+/// A [Task] that is due at `at`, ordered so that the earliest entry is at the top of a [BinaryHeap].
+struct Entry {
+    at: Instant,
+    task: Task,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.at == other.at
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.at.cmp(&self.at)
+    }
+}
+
+/// Runs the tasks of all requests with timeouts on a single thread, in the order they are due.
+#[derive(Default)]
+struct Timer {
+    entries: Mutex<BinaryHeap<Entry>>,
+    condvar: Condvar,
+}
+
+impl Timer {
+    /// Returns the timer, which is started when it is first used.
+    fn shared() -> &'static Timer {
+        static TIMER: OnceLock<Timer> = OnceLock::new();
+        let mut created = false;
+        let timer = TIMER.get_or_init(|| {
+            created = true;
+            Timer::default()
+        });
+        if created {
+            thread::Builder::new()
+                .name(String::from("cronet-rs-timer"))
+                .spawn(|| timer.run())
+                .expect("failed to spawn the timer thread");
+        }
+        timer
+    }
+
+    fn schedule(&self, entry: Entry) {
+        self.entries.lock().unwrap().push(entry);
+        self.condvar.notify_one();
+    }
+
+    fn run(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            entries = match entries.peek().map(|entry| entry.at) {
+                None => self.condvar.wait(entries).unwrap(),
+                Some(at) if at > now => self.condvar.wait_timeout(entries, at - now).unwrap().0,
+                Some(_) => {
+                    let entry = entries.pop().unwrap();
+                    // Tasks call into Cronet, so new entries can be scheduled meanwhile.
+                    drop(entries);
+                    // A panicking task must not stop the timer, which is shared by all requests.
+                    let next = unwind::catch(|| entry.task.run(now)).ok().flatten();
+                    let mut entries = self.entries.lock().unwrap();
+                    entries.extend(next);
+                    entries
+                }
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BinaryHeap,
+        time::{Duration, Instant},
+    };
+
+    use crate::{client::RequestHandle, UrlRequest};
+
+    use super::{Entry, Task, TimeoutPhase};
+
+    #[test]
+    fn it_runs_the_earliest_entry_first() {
+        let now = Instant::now();
+        let handle = RequestHandle::new(UrlRequest::new());
+        let entry = |millis| Entry {
+            at: now + Duration::from_millis(millis),
+            task: Task::Cancel {
+                handle: handle.clone(),
+                phase: TimeoutPhase::Headers,
+            },
+        };
+        let mut entries = BinaryHeap::from([entry(20), entry(10), entry(30)]);
+        assert_eq!(entries.pop().unwrap().at, now + Duration::from_millis(10));
+        assert_eq!(entries.pop().unwrap().at, now + Duration::from_millis(20));
+    }
+
+    #[test]
+    fn it_stops_watching_finished_requests() {
+        let handle = RequestHandle::new(UrlRequest::new());
+        handle.release();
+        let task = Task::Watch {
+            handle,
+            deadline: None,
+            read_timeout: Some(Duration::from_millis(10)),
+        };
+        assert!(task.run(Instant::now()).is_none());
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use crate::Cronet_ClientContext;
+
+/// Moves `handler` into a client context, so that it can be stored on the native object that dispatches to it.
+/// The handler is kept alive until [release] is called.
+pub(crate) fn into_raw<T>(handler: T) -> Cronet_ClientContext {
+    Arc::into_raw(Arc::new(handler)) as Cronet_ClientContext
+}
+
+/// Returns the handler stored in a client context by [into_raw], or `None` if it was released.
+///
+/// The returned handler stays alive even if the native object is destroyed while it is in use,
+/// e.g. by the handler itself.
+///
+/// # Safety
+///
+/// `client_context` must be null or have been created by [into_raw] with the same `T`.
+pub(crate) unsafe fn get<T>(client_context: Cronet_ClientContext) -> Option<Arc<T>> {
+    if client_context.is_null() {
+        return None;
+    }
+    let handler = client_context as *const T;
+    Arc::increment_strong_count(handler);
+    Some(Arc::from_raw(handler))
+}
+
+/// Takes back the handler stored in a client context by [into_raw], which is dropped once it is no longer in use.
+///
+/// # Safety
+///
+/// `client_context` must be null or have been created by [into_raw] with the same `T`,
+/// and must not be used afterwards.
+pub(crate) unsafe fn release<T>(client_context: Cronet_ClientContext) -> Option<Arc<T>> {
+    if client_context.is_null() {
+        return None;
+    }
+    Some(Arc::from_raw(client_context as *const T))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    #[test]
+    fn it_keeps_handlers_alive_while_in_use() {
+        let context = super::into_raw(String::from("handler"));
+        unsafe {
+            let handler = super::get::<String>(context).unwrap();
+            let released = super::release::<String>(context).unwrap();
+            drop(released);
+            assert_eq!(handler.as_str(), "handler");
+            assert_eq!(Arc::strong_count(&handler), 1);
+            assert!(super::get::<String>(std::ptr::null_mut()).is_none());
+        }
+    }
+}
@@ -2,10 +2,9 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::{
     Cronet_DateTimePtr, Cronet_DateTime_Create, Cronet_DateTime_Destroy, Cronet_DateTime_value_get,
-    Cronet_DateTime_value_set, Destroy,
+    Cronet_DateTime_value_set,
 };
 
-#[derive(Copy, Clone)]
 pub struct DateTime {
     pub(crate) ptr: Cronet_DateTimePtr,
 }
@@ -42,8 +41,8 @@ impl DateTime {
     }
 }
 
-impl Destroy for DateTime {
-    fn destroy(&self) {
+impl Drop for DateTime {
+    fn drop(&mut self) {
         unsafe { Cronet_DateTime_Destroy(self.ptr) }
     }
 }
@@ -58,8 +57,6 @@ impl Default for DateTime {
 mod tests {
     use std::time::UNIX_EPOCH;
 
-    use crate::Destroy;
-
     #[test]
     fn test_date_time() {
         let date_time = super::DateTime::new();
@@ -69,7 +66,6 @@ mod tests {
         let now2 = date_time.get();
         let now2_millis = now2.duration_since(UNIX_EPOCH).unwrap().as_millis();
         assert_eq!(now_millis, now2_millis);
-        date_time.destroy();
     }
 
     #[test]
@@ -79,6 +75,5 @@ mod tests {
         date_time.set_millis(millis);
         let millis2 = date_time.millis();
         assert_eq!(millis, millis2);
-        date_time.destroy();
     }
 }
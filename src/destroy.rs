@@ -1,3 +0,0 @@
-pub trait Destroy {
-    fn destroy(&self);
-}
@@ -1,10 +1,16 @@
-use std::ffi::{CStr, CString};
+use std::{
+    borrow::Cow,
+    ffi::CString,
+    sync::{Arc, Mutex},
+};
 
 use crate::{
-    Cronet_EnginePtr, Cronet_Engine_Create, Cronet_Engine_Destroy,
-    Cronet_Engine_GetDefaultUserAgent, Cronet_Engine_GetVersionString, Cronet_Engine_Shutdown,
-    Cronet_Engine_StartNetLogToFile, Cronet_Engine_StartWithParams, Cronet_Engine_StopNetLog,
-    Destroy, EngineParams, EngineResult,
+    executor, string, unwind, Cronet_EnginePtr, Cronet_Engine_AddRequestFinishedListener,
+    Cronet_Engine_Create, Cronet_Engine_Destroy, Cronet_Engine_GetDefaultUserAgent,
+    Cronet_Engine_GetVersionString, Cronet_Engine_RemoveRequestFinishedListener,
+    Cronet_Engine_Shutdown, Cronet_Engine_StartNetLogToFile, Cronet_Engine_StartWithParams,
+    Cronet_Engine_StopNetLog, EngineParams, EngineResult, Executor, RequestFinishedInfoListener,
+    Runnable,
 };
 
 pub struct Engine {
@@ -27,7 +33,6 @@ impl Engine {
     pub fn start(&self, params: EngineParams) -> EngineResult {
         unsafe {
             let result = Cronet_Engine_StartWithParams(self.ptr, params.ptr);
-            params.destroy();
             EngineResult::try_from(result).unwrap()
         }
     }
@@ -40,11 +45,11 @@ impl Engine {
     /// Arguments:
     ///
     /// * `file_name`: The complete file path.
-    ///                It must not be empty and if the file exists, it is truncated before starting.
-    ///                If actively logging, this method is ignored.
-    /// * `log_all`:   Include basic events, user cookies, credentials and all transferred bytes in the log.
-    ///                This option presents a privacy risk, since it exposes the user's credentials, and should only be used with the user's consent and in situations where the log won't be public.
-    ///                Set to `false` to only include basic events.
+    ///   It must not be empty and if the file exists, it is truncated before starting.
+    ///   If actively logging, this method is ignored.
+    /// * `log_all`: Include basic events, user cookies, credentials and all transferred bytes in the log.
+    ///   This option presents a privacy risk, since it exposes the user's credentials, and should only be used with the user's consent and in situations where the log won't be public.
+    ///   Set to `false` to only include basic events.
     ///
     /// Returns `true` if netlog has started successfully, `false` otherwise.
     pub fn start_net_log(&self, file_name: &str, log_all: bool) {
@@ -66,8 +71,14 @@ impl Engine {
     /// Shut down the Engine if there are no active requests, otherwise returns a failure Result.
     ///
     /// Note that this method cannot be called on a network thread - the thread Cronet calls into [Executor] (which is different from the thread the Executor invokes callbacks on).
+    /// Calling it while Cronet posts to an [Executor], including from callbacks that such an executor runs inline,
+    /// returns [EngineResult::IllegalStateCannotShutdownEngineFromNetworkThread] without shutting down.
     /// This method blocks until all the Engine's resources have been cleaned up.
+    /// [crate::client::Client] takes care of this by shutting down on another thread if needed.
     pub fn shutdown(&self) -> EngineResult {
+        if executor::on_network_thread() {
+            return EngineResult::IllegalStateCannotShutdownEngineFromNetworkThread;
+        }
         unsafe {
             let result = Cronet_Engine_Shutdown(self.ptr);
             EngineResult::try_from(result).unwrap()
@@ -75,41 +86,147 @@ impl Engine {
     }
 
     /// A human-readable version string of the engine.
-    pub fn version(&self) -> &str {
-        unsafe {
-            let version = Cronet_Engine_GetVersionString(self.ptr);
-            let version = CStr::from_ptr(version);
-            version.to_str().unwrap()
-        }
+    pub fn version(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_Engine_GetVersionString(self.ptr)) }
     }
 
     /// Returns the default value of the `User-Agent` header.
     /// Can be accessed before `StartWithParams()` is called.
-    pub fn default_user_agent(&self) -> &str {
+    pub fn default_user_agent(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_Engine_GetDefaultUserAgent(self.ptr)) }
+    }
+
+    /// Registers a listener that gets called at the end of each request, on the given [Executor].
+    /// The listener receives the [crate::RequestFinishedInfo] of every request of this engine,
+    /// including its [crate::Metrics] and annotations.
+    ///
+    /// The listener is unregistered when the returned [RequestFinishedListenerRegistration] is dropped.
+    pub fn add_request_finished_listener(
+        &self,
+        listener: RequestFinishedInfoListener,
+        executor: Executor,
+    ) -> RequestFinishedListenerRegistration<'_> {
+        let registered = Arc::new(RegisteredListener {
+            listener,
+            executor,
+            invocations: Mutex::new(Invocations::default()),
+        });
+        // Each invocation keeps the listener and executor alive until it has run or was dropped.
+        let forwarded = Arc::clone(&registered);
+        let forwarding = Executor::new(move |_, runnable| {
+            let invocation = Invocation::new(Arc::clone(&forwarded));
+            let mut runnable = Some(runnable);
+            forwarded.executor.execute(Runnable::new(move |_| {
+                let _invocation = &invocation;
+                if let Some(runnable) = runnable.take() {
+                    runnable.run();
+                }
+            }));
+        });
         unsafe {
-            let version = Cronet_Engine_GetDefaultUserAgent(self.ptr);
-            let version = CStr::from_ptr(version);
-            version.to_str().unwrap()
+            Cronet_Engine_AddRequestFinishedListener(
+                self.ptr,
+                registered.listener.ptr,
+                forwarding.ptr,
+            );
+        }
+        RequestFinishedListenerRegistration {
+            engine: self,
+            registered,
+            forwarding: Some(forwarding),
         }
     }
+}
+
+/// A [RequestFinishedInfoListener] registered with [Engine::add_request_finished_listener].
+///
+/// Dropping it unregisters the listener. The listener and its [Executor] are destroyed
+/// once every invocation posted to the executor has run or was dropped.
+pub struct RequestFinishedListenerRegistration<'a> {
+    engine: &'a Engine,
+    registered: Arc<RegisteredListener>,
+    /// The executor passed to Cronet, which posts each invocation to the executor of the app.
+    forwarding: Option<Executor>,
+}
+
+struct RegisteredListener {
+    listener: RequestFinishedInfoListener,
+    executor: Executor,
+    invocations: Mutex<Invocations>,
+}
 
-    pub fn add_request_finished_listener(&self) {
-        unimplemented!() // TODO: implement me
+/// The invocations of a [RegisteredListener] that were posted to its executor, but haven't run or been dropped yet.
+#[derive(Default)]
+struct Invocations {
+    pending: usize,
+    /// The forwarding executor of a removed listener, which is released once no invocations are pending.
+    retired: Option<Executor>,
+}
+
+/// Counts as a pending invocation of a listener until it is dropped.
+struct Invocation(Arc<RegisteredListener>);
+
+impl Invocation {
+    fn new(registered: Arc<RegisteredListener>) -> Self {
+        unwind::lock(&registered.invocations).pending += 1;
+        Self(registered)
+    }
+}
+
+impl Drop for Invocation {
+    fn drop(&mut self) {
+        let retired = {
+            let mut invocations = unwind::lock(&self.0.invocations);
+            invocations.pending -= 1;
+            match invocations.pending {
+                0 => invocations.retired.take(),
+                _ => None,
+            }
+        };
+        drop(retired);
+    }
+}
+
+// Cronet allows listeners and executors to be used from any thread.
+unsafe impl Send for RegisteredListener {}
+unsafe impl Sync for RegisteredListener {}
+
+impl RequestFinishedListenerRegistration<'_> {
+    pub fn listener(&self) -> &RequestFinishedInfoListener {
+        &self.registered.listener
     }
 
-    pub fn remove_request_finished_listener(&self) {
-        unimplemented!() // TODO: implement me
+    pub fn executor(&self) -> &Executor {
+        &self.registered.executor
     }
 }
 
-impl Destroy for Engine {
-    fn destroy(&self) {
+impl Drop for RequestFinishedListenerRegistration<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            Cronet_Engine_RemoveRequestFinishedListener(
+                self.engine.ptr,
+                self.registered.listener.ptr,
+            );
+        }
+        // The forwarding executor is released by the last pending invocation, if there is any.
+        let mut invocations = unwind::lock(&self.registered.invocations);
+        if invocations.pending > 0 {
+            invocations.retired = self.forwarding.take();
+        }
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
         unsafe {
             Cronet_Engine_Destroy(self.ptr);
         }
     }
 }
 
+unsafe impl Send for Engine {}
+
 impl Default for Engine {
     fn default() -> Self {
         Self::new()
@@ -118,14 +235,12 @@ impl Default for Engine {
 
 #[cfg(test)]
 mod tests {
-    use crate::Destroy;
 
     #[test]
     fn it_gets_version() {
         let engine = super::Engine::new();
         let version = engine.version();
         assert!(!version.is_empty());
-        engine.destroy();
     }
 
     #[test]
@@ -133,7 +248,6 @@ mod tests {
         let engine = super::Engine::new();
         let default_user_agent = engine.default_user_agent();
         assert!(!default_user_agent.is_empty());
-        engine.destroy();
     }
 
     #[test]
@@ -144,13 +258,76 @@ mod tests {
         assert_eq!(result, crate::EngineResult::Success);
         let result = engine.shutdown();
         assert_eq!(result, crate::EngineResult::Success);
-        engine.destroy();
     }
 
     #[test]
-    fn test_engine() {
+    fn it_refuses_to_shut_down_from_the_network_thread() {
+        use std::sync::{mpsc, Arc, Mutex};
+
+        let engine = Arc::new(Mutex::new(super::Engine::new()));
+        let result = engine.lock().unwrap().start(crate::EngineParams::new());
+        assert_eq!(result, crate::EngineResult::Success);
+        let (tx, rx) = mpsc::channel();
+        let shared = Arc::clone(&engine);
+        let executor = crate::Executor::new(move |_, _| {
+            tx.send(shared.lock().unwrap().shutdown()).unwrap();
+        });
+        executor.execute(crate::Runnable::new(|_| {}));
+
+        assert_eq!(
+            rx.recv().unwrap(),
+            crate::EngineResult::IllegalStateCannotShutdownEngineFromNetworkThread
+        );
+        assert_eq!(
+            engine.lock().unwrap().shutdown(),
+            crate::EngineResult::Success
+        );
+    }
+
+    #[test]
+    fn it_adds_and_removes_request_finished_listener() {
+        let engine = super::Engine::new();
+        let listener = crate::RequestFinishedInfoListener::new(|_, _, _, _| {});
+        let executor = crate::Executor::new(|_, runnable| runnable.run());
+        let registration = engine.add_request_finished_listener(listener, executor);
+        assert!(!registration.listener().ptr.is_null());
+        let registered = std::sync::Arc::downgrade(&registration.registered);
+        drop(registration);
+        assert!(registered.upgrade().is_none());
+    }
+
+    #[test]
+    fn it_keeps_the_listener_until_posted_invocations_ran() {
+        use std::sync::{mpsc, Arc, Mutex};
+
         let engine = super::Engine::new();
+        let listener = crate::RequestFinishedInfoListener::new(|_, _, _, _| {});
+        let queue = Arc::new(Mutex::new(Vec::new()));
+        let posted = Arc::clone(&queue);
+        let executor =
+            crate::Executor::new(move |_, runnable| posted.lock().unwrap().push(runnable));
+        let registration = engine.add_request_finished_listener(listener, executor);
+        let registered = Arc::downgrade(&registration.registered);
+        let (tx, rx) = mpsc::channel();
+        registration
+            .forwarding
+            .as_ref()
+            .unwrap()
+            .execute(crate::Runnable::new(move |_| tx.send(()).unwrap()));
+        drop(registration);
+
+        assert!(registered.upgrade().is_some());
+        for runnable in queue.lock().unwrap().drain(..) {
+            runnable.run();
+        }
+        assert!(rx.try_recv().is_ok());
+        assert!(registered.upgrade().is_none());
+        drop(engine);
+    }
+
+    #[test]
+    fn test_engine() {
+        let _engine = super::Engine::new();
         // TODO: add full E2E engine test
-        engine.destroy();
     }
 }
@@ -1,11 +1,12 @@
-use std::ffi::{CStr, CString};
+use std::{borrow::Cow, ffi::CString};
 
 use crate::{
-    Cronet_EngineParamsPtr, Cronet_EngineParams_Create, Cronet_EngineParams_Destroy,
-    Cronet_EngineParams_accept_language_get, Cronet_EngineParams_accept_language_set,
-    Cronet_EngineParams_enable_brotli_get, Cronet_EngineParams_enable_brotli_set,
-    Cronet_EngineParams_enable_check_result_get, Cronet_EngineParams_enable_check_result_set,
-    Cronet_EngineParams_enable_http2_get, Cronet_EngineParams_enable_http2_set,
+    string, Borrowed, Cronet_EngineParamsPtr, Cronet_EngineParams_Create,
+    Cronet_EngineParams_Destroy, Cronet_EngineParams_accept_language_get,
+    Cronet_EngineParams_accept_language_set, Cronet_EngineParams_enable_brotli_get,
+    Cronet_EngineParams_enable_brotli_set, Cronet_EngineParams_enable_check_result_get,
+    Cronet_EngineParams_enable_check_result_set, Cronet_EngineParams_enable_http2_get,
+    Cronet_EngineParams_enable_http2_set,
     Cronet_EngineParams_enable_public_key_pinning_bypass_for_local_trust_anchors_get,
     Cronet_EngineParams_enable_public_key_pinning_bypass_for_local_trust_anchors_set,
     Cronet_EngineParams_enable_quic_get, Cronet_EngineParams_enable_quic_set,
@@ -18,7 +19,7 @@ use crate::{
     Cronet_EngineParams_quic_hints_at, Cronet_EngineParams_quic_hints_clear,
     Cronet_EngineParams_quic_hints_size, Cronet_EngineParams_storage_path_get,
     Cronet_EngineParams_storage_path_set, Cronet_EngineParams_user_agent_get,
-    Cronet_EngineParams_user_agent_set, Destroy, PublicKeyPins, QuicHint,
+    Cronet_EngineParams_user_agent_set, PublicKeyPins, QuicHint,
 };
 
 /// Encapsulates configuration parameters for the [Engine].
@@ -57,13 +58,8 @@ impl EngineParams {
     }
 
     /// Returns the `User-Agent` header value.
-    pub fn user_agent(&self) -> &str {
-        unsafe {
-            let c_str = Cronet_EngineParams_user_agent_get(self.ptr);
-            let c_str = CStr::from_ptr(c_str);
-            let str_slice = c_str.to_str().unwrap();
-            str_slice
-        }
+    pub fn user_agent(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_EngineParams_user_agent_get(self.ptr)) }
     }
 
     /// Set the default `Accept-Language` header value for all requests.
@@ -75,13 +71,8 @@ impl EngineParams {
     }
 
     /// Returns the `Accept-Language` header value.
-    pub fn accept_language(&self) -> &str {
-        unsafe {
-            let c_str = Cronet_EngineParams_accept_language_get(self.ptr);
-            let c_str = CStr::from_ptr(c_str);
-            let str_slice = c_str.to_str().unwrap();
-            str_slice
-        }
+    pub fn accept_language(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_EngineParams_accept_language_get(self.ptr)) }
     }
 
     /// Set the directory for HTTP Cache and Prefs Storage.
@@ -94,13 +85,8 @@ impl EngineParams {
     }
 
     /// Returns the directory for HTTP Cache and Prefs Storage.
-    pub fn storage_path(&self) -> &str {
-        unsafe {
-            let c_str = Cronet_EngineParams_storage_path_get(self.ptr);
-            let c_str = CStr::from_ptr(c_str);
-            let str_slice = c_str.to_str().unwrap();
-            str_slice
-        }
+    pub fn storage_path(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_EngineParams_storage_path_get(self.ptr)) }
     }
 
     /// Whether the <a href="https://www.chromium.org/quic">QUIC</a> protocol should be enabled.
@@ -169,7 +155,8 @@ impl EngineParams {
         unsafe { Cronet_EngineParams_http_cache_max_size_get(self.ptr) }
     }
 
-    pub fn add_quic_hint(&self, quic_hint: QuicHint) {
+    /// Adds a copy of `quic_hint` to the hints of the engine.
+    pub fn add_quic_hint(&self, quic_hint: &QuicHint) {
         unsafe {
             Cronet_EngineParams_quic_hints_add(self.ptr, quic_hint.ptr);
         }
@@ -179,10 +166,10 @@ impl EngineParams {
         unsafe { Cronet_EngineParams_quic_hints_size(self.ptr) }
     }
 
-    pub fn quic_hint_at(&self, index: u32) -> QuicHint {
+    pub fn quic_hint_at(&self, index: u32) -> Borrowed<'_, QuicHint> {
         unsafe {
             let ptr = Cronet_EngineParams_quic_hints_at(self.ptr, index);
-            QuicHint { ptr }
+            Borrowed::new(QuicHint { ptr })
         }
     }
 
@@ -192,7 +179,8 @@ impl EngineParams {
         }
     }
 
-    pub fn add_public_key_pins(&self, public_key_pins: PublicKeyPins) {
+    /// Adds a copy of `public_key_pins` to the pins of the engine.
+    pub fn add_public_key_pins(&self, public_key_pins: &PublicKeyPins) {
         unsafe {
             Cronet_EngineParams_public_key_pins_add(self.ptr, public_key_pins.ptr);
         }
@@ -202,10 +190,10 @@ impl EngineParams {
         unsafe { Cronet_EngineParams_public_key_pins_size(self.ptr) }
     }
 
-    pub fn public_key_pins_at(&self, index: u32) -> PublicKeyPins {
+    pub fn public_key_pins_at(&self, index: u32) -> Borrowed<'_, PublicKeyPins> {
         unsafe {
             let ptr = Cronet_EngineParams_public_key_pins_at(self.ptr, index);
-            PublicKeyPins { ptr }
+            Borrowed::new(PublicKeyPins { ptr })
         }
     }
 
@@ -257,18 +245,13 @@ impl EngineParams {
         }
     }
 
-    pub fn experimental_options(&self) -> &str {
-        unsafe {
-            let c_str = Cronet_EngineParams_experimental_options_get(self.ptr);
-            let c_str = CStr::from_ptr(c_str);
-            let str_slice = c_str.to_str().unwrap();
-            str_slice
-        }
+    pub fn experimental_options(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_EngineParams_experimental_options_get(self.ptr)) }
     }
 }
 
-impl Destroy for EngineParams {
-    fn destroy(&self) {
+impl Drop for EngineParams {
+    fn drop(&mut self) {
         unsafe { Cronet_EngineParams_Destroy(self.ptr) }
     }
 }
@@ -279,7 +262,7 @@ impl Default for EngineParams {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum HttpCacheMode {
     /// Disable HTTP cache.
     /// Some data may still be temporarily stored in memory.
@@ -313,14 +296,12 @@ impl TryFrom<i64> for HttpCacheMode {
 
 #[cfg(test)]
 mod tests {
-    use crate::Destroy;
 
     #[test]
     fn it_enables_check_result() {
         let engine_params = super::EngineParams::new();
         engine_params.set_enable_check_result(true);
-        assert_eq!(engine_params.enable_check_result(), true);
-        engine_params.destroy();
+        assert!(engine_params.enable_check_result());
     }
 
     #[test]
@@ -328,7 +309,6 @@ mod tests {
         let engine_params = super::EngineParams::new();
         engine_params.set_user_agent("test");
         assert_eq!(engine_params.user_agent(), "test");
-        engine_params.destroy();
     }
 
     #[test]
@@ -336,7 +316,6 @@ mod tests {
         let engine_params = super::EngineParams::new();
         engine_params.set_accept_language("test");
         assert_eq!(engine_params.accept_language(), "test");
-        engine_params.destroy();
     }
 
     #[test]
@@ -344,31 +323,27 @@ mod tests {
         let engine_params = super::EngineParams::new();
         engine_params.set_storage_path("test");
         assert_eq!(engine_params.storage_path(), "test");
-        engine_params.destroy();
     }
 
     #[test]
     fn it_enables_quic() {
         let engine_params = super::EngineParams::new();
         engine_params.set_enable_quic(true);
-        assert_eq!(engine_params.enable_quic(), true);
-        engine_params.destroy();
+        assert!(engine_params.enable_quic());
     }
 
     #[test]
     fn it_enables_http_2() {
         let engine_params = super::EngineParams::new();
         engine_params.set_enable_http_2(true);
-        assert_eq!(engine_params.enable_http_2(), true);
-        engine_params.destroy();
+        assert!(engine_params.enable_http_2());
     }
 
     #[test]
     fn it_enables_brotli() {
         let engine_params = super::EngineParams::new();
         engine_params.set_enable_brotli(true);
-        assert_eq!(engine_params.enable_brotli(), true);
-        engine_params.destroy();
+        assert!(engine_params.enable_brotli());
     }
 
     #[test]
@@ -379,7 +354,6 @@ mod tests {
             engine_params.http_cache_mode(),
             super::HttpCacheMode::Disabled
         );
-        engine_params.destroy();
     }
 
     #[test]
@@ -387,7 +361,6 @@ mod tests {
         let engine_params = super::EngineParams::new();
         engine_params.set_http_cache_max_size(10);
         assert_eq!(engine_params.http_cache_max_size(), 10);
-        engine_params.destroy();
     }
 
     #[test]
@@ -395,7 +368,6 @@ mod tests {
         let engine_params = super::EngineParams::new();
         engine_params.set_experimental_options("test");
         assert_eq!(engine_params.experimental_options(), "test");
-        engine_params.destroy();
     }
 
     #[test]
@@ -403,18 +375,13 @@ mod tests {
         let engine_params = super::EngineParams::new();
         engine_params.set_network_thread_priority(10.0);
         assert_eq!(engine_params.network_thread_priority(), 10.0);
-        engine_params.destroy();
     }
 
     #[test]
     fn it_enables_public_key_pinning_bypass_for_local_trust_anchors() {
         let engine_params = super::EngineParams::new();
         engine_params.set_enable_public_key_pinning_bypass_for_local_trust_anchors(true);
-        assert_eq!(
-            engine_params.enable_public_key_pinning_bypass_for_local_trust_anchors(),
-            true
-        );
-        engine_params.destroy();
+        assert!(engine_params.enable_public_key_pinning_bypass_for_local_trust_anchors());
     }
 
     #[test]
@@ -422,13 +389,12 @@ mod tests {
         let engine_params = super::EngineParams::new();
         let quic_hint = super::QuicHint::new();
         quic_hint.set_host("www.example.com");
-        engine_params.add_quic_hint(quic_hint);
+        engine_params.add_quic_hint(&quic_hint);
         assert_eq!(engine_params.quic_hints_size(), 1);
         let quic_hint2 = engine_params.quic_hint_at(0);
         assert_eq!(quic_hint2.host(), "www.example.com");
         engine_params.clear_quic_hints();
         assert_eq!(engine_params.quic_hints_size(), 0);
-        engine_params.destroy();
     }
 
     #[test]
@@ -436,7 +402,7 @@ mod tests {
         let engine_params = super::EngineParams::new();
         let public_key_pins = super::PublicKeyPins::new();
         public_key_pins.add("sha256/AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=");
-        engine_params.add_public_key_pins(public_key_pins);
+        engine_params.add_public_key_pins(&public_key_pins);
         assert_eq!(engine_params.public_key_pins_size(), 1);
         let public_key_pins2 = engine_params.public_key_pins_at(0);
         assert_eq!(
@@ -445,6 +411,5 @@ mod tests {
         );
         engine_params.clear_public_key_pins();
         assert_eq!(engine_params.public_key_pins_size(), 0);
-        engine_params.destroy();
     }
 }
@@ -1,14 +1,12 @@
-use std::{
-    ffi::{CStr, CString},
-    fmt,
-};
+use std::{borrow::Cow, ffi::CString, fmt};
 
 use crate::{
-    Cronet_ErrorPtr, Cronet_Error_Create, Cronet_Error_Destroy, Cronet_Error_error_code_get,
-    Cronet_Error_error_code_set, Cronet_Error_immediately_retryable_get,
-    Cronet_Error_immediately_retryable_set, Cronet_Error_internal_error_code_get,
-    Cronet_Error_internal_error_code_set, Cronet_Error_message_get, Cronet_Error_message_set,
-    Cronet_Error_quic_detailed_error_code_get, Cronet_Error_quic_detailed_error_code_set, Destroy,
+    string, Cronet_ErrorPtr, Cronet_Error_Create, Cronet_Error_Destroy,
+    Cronet_Error_error_code_get, Cronet_Error_error_code_set,
+    Cronet_Error_immediately_retryable_get, Cronet_Error_immediately_retryable_set,
+    Cronet_Error_internal_error_code_get, Cronet_Error_internal_error_code_set,
+    Cronet_Error_message_get, Cronet_Error_message_set, Cronet_Error_quic_detailed_error_code_get,
+    Cronet_Error_quic_detailed_error_code_set,
 };
 
 #[derive(Debug)]
@@ -49,12 +47,8 @@ impl CronetError {
     }
 
     /// Get the error message.
-    pub fn message(&self) -> &str {
-        unsafe {
-            let c_message = Cronet_Error_message_get(self.ptr);
-            let message = CStr::from_ptr(c_message).to_str().unwrap();
-            message
-        }
+    pub fn message(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_Error_message_get(self.ptr)) }
     }
 
     /// Set the internal error code.
@@ -108,14 +102,31 @@ impl CronetError {
     }
 }
 
-impl Destroy for CronetError {
-    fn destroy(&self) {
+impl Drop for CronetError {
+    fn drop(&mut self) {
         unsafe {
             Cronet_Error_Destroy(self.ptr);
         }
     }
 }
 
+unsafe impl Send for CronetError {}
+
+/// Copies the error, so it can be used after the request that reported it is destroyed.
+impl Clone for CronetError {
+    fn clone(&self) -> Self {
+        let error = CronetError::new();
+        unsafe {
+            Cronet_Error_error_code_set(error.ptr, Cronet_Error_error_code_get(self.ptr));
+        }
+        error.set_message(&self.message());
+        error.set_internal_error_code(self.internal_error_code());
+        error.set_retryable(self.retryable());
+        error.set_quic_detailed_error_code(self.quic_detailed_error_code());
+        error
+    }
+}
+
 impl Default for CronetError {
     fn default() -> Self {
         Self::new()
@@ -133,7 +144,7 @@ impl fmt::Display for CronetError {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ErrorCode {
     /// Error returned by app callback.
     Callback = 0,
@@ -199,7 +210,6 @@ impl TryFrom<u32> for ErrorCode {
 
 #[cfg(test)]
 mod tests {
-    use crate::Destroy;
 
     #[test]
     fn test_cronet_error() {
@@ -212,8 +222,19 @@ mod tests {
         assert_eq!(cronet_error.error_code(), super::ErrorCode::TimedOut);
         assert_eq!(cronet_error.message(), "test");
         assert_eq!(cronet_error.internal_error_code(), 1337);
-        assert_eq!(cronet_error.retryable(), true);
+        assert!(cronet_error.retryable());
         assert_eq!(cronet_error.quic_detailed_error_code(), 10);
-        cronet_error.destroy();
+    }
+
+    #[test]
+    fn it_clones_cronet_error() {
+        let cronet_error = super::CronetError::new();
+        cronet_error.set_error_code(super::ErrorCode::NetworkChanged);
+        cronet_error.set_message("test");
+        cronet_error.set_retryable(true);
+        let clone = cronet_error.clone();
+        assert_eq!(clone.error_code(), super::ErrorCode::NetworkChanged);
+        assert_eq!(clone.message(), "test");
+        assert!(clone.retryable());
     }
 }
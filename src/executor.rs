@@ -1,64 +1,100 @@
-use once_cell::sync::Lazy;
+use std::{cell::Cell, sync::Arc};
 
 use crate::{
-    state::CronetCallbacks, Cronet_ExecutorPtr, Cronet_Executor_CreateWith,
-    Cronet_Executor_Destroy, Cronet_Executor_Execute, Cronet_RunnablePtr, Destroy, Runnable,
+    client_context, unwind, Borrowed, Cronet_ExecutorPtr, Cronet_Executor_CreateWith,
+    Cronet_Executor_Destroy, Cronet_Executor_Execute, Cronet_Executor_GetClientContext,
+    Cronet_Executor_SetClientContext, Cronet_RunnablePtr, Runnable,
 };
 
-static mut EXECUTOR_CALLBACKS: Lazy<CronetCallbacks<Cronet_ExecutorPtr, ExecutorExecuteFn>> =
-    Lazy::new(CronetCallbacks::new);
+type ExecutorHandler = Box<ExecutorExecuteFn>;
+
+thread_local! {
+    /// Whether the current thread is posting a runnable, which Cronet does from its network thread.
+    static IN_EXECUTE: Cell<bool> = const { Cell::new(false) };
+}
 
 #[no_mangle]
 unsafe extern "C" fn cronetExecutorOnExecute(
     selfPtr: Cronet_ExecutorPtr,
     runnablePtr: Cronet_RunnablePtr,
 ) {
-    let lockedMap = EXECUTOR_CALLBACKS.map().lock().unwrap();
-    if let Some(callback) = lockedMap.get(&selfPtr) {
-        callback(Executor { ptr: selfPtr }, Runnable { ptr: runnablePtr });
+    let callback: Option<Arc<ExecutorHandler>> =
+        client_context::get(Cronet_Executor_GetClientContext(selfPtr));
+    if let Some(callback) = callback {
+        let outer = IN_EXECUTE.with(|in_execute| in_execute.replace(true));
+        // The executor takes ownership of the runnable, which is dropped without running if it panics.
+        let _ = unwind::catch(|| {
+            callback(
+                Borrowed::new(Executor { ptr: selfPtr }),
+                Runnable { ptr: runnablePtr },
+            )
+        });
+        IN_EXECUTE.with(|in_execute| in_execute.set(outer));
     }
 }
 
-pub type ExecutorExecuteFn = fn(executor: Executor, runnable: Runnable);
+/// Whether the current thread may be a network thread of Cronet,
+/// i.e. it is posting a runnable to an [Executor] which may run it inline.
+///
+/// [crate::Engine::shutdown] must not be called on such a thread.
+pub(crate) fn on_network_thread() -> bool {
+    IN_EXECUTE.with(Cell::get)
+}
+
+pub type ExecutorExecuteFn = dyn Fn(Borrowed<'_, Executor>, Runnable) + Send + Sync;
 
 /// An interface provided by the app to run a [Runnable] asynchronously.
+///
+/// The executor takes ownership of each [Runnable] it is given, and must run it and then drop it.
 pub struct Executor {
     pub(crate) ptr: Cronet_ExecutorPtr,
 }
 
 impl Executor {
-    pub fn new(on_execute: ExecutorExecuteFn) -> Self {
+    pub fn new<F>(on_execute: F) -> Self
+    where
+        F: Fn(Borrowed<'_, Executor>, Runnable) + Send + Sync + 'static,
+    {
         unsafe {
             let ptr = Cronet_Executor_CreateWith(Some(cronetExecutorOnExecute));
-            EXECUTOR_CALLBACKS
-                .map()
-                .lock()
-                .unwrap()
-                .insert(ptr, on_execute);
+            let handler: ExecutorHandler = Box::new(on_execute);
+            Cronet_Executor_SetClientContext(ptr, client_context::into_raw(handler));
             Self { ptr }
         }
     }
 
+    /// Creates an executor that runs each [Runnable] on the blocking thread pool of a Tokio runtime,
+    /// so callbacks never run on (and never block) the network thread of Cronet.
+    ///
+    /// Runnables that are still queued when the runtime shuts down are dropped without running.
+    #[cfg(feature = "tokio")]
+    pub fn new_with_tokio(handle: tokio::runtime::Handle) -> Self {
+        Self::new(move |_, runnable| {
+            handle.spawn_blocking(move || runnable.run());
+        })
+    }
+
+    /// Passes the runnable to the executor, which takes ownership of it.
     pub fn execute(&self, runnable: Runnable) {
         unsafe {
-            Cronet_Executor_Execute(self.ptr, runnable.ptr);
+            Cronet_Executor_Execute(self.ptr, runnable.into_raw());
         }
     }
 }
 
-impl Destroy for Executor {
-    fn destroy(&self) {
+impl Drop for Executor {
+    fn drop(&mut self) {
         unsafe {
-            let mut lockedMap = EXECUTOR_CALLBACKS.map().lock().unwrap();
-            lockedMap.remove(&self.ptr);
+            client_context::release::<ExecutorHandler>(Cronet_Executor_GetClientContext(self.ptr));
             Cronet_Executor_Destroy(self.ptr)
         }
     }
 }
 
+unsafe impl Send for Executor {}
+
 #[cfg(test)]
 mod tests {
-    use crate::Destroy;
 
     #[test]
     fn test_executor() {
@@ -66,6 +102,32 @@ mod tests {
         let executor = super::Executor::new(|_, _| {});
         let runnable = super::Runnable::new(|_| {});
         executor.execute(runnable);
-        executor.destroy();
+    }
+
+    #[test]
+    fn it_marks_the_posting_thread() {
+        let executor = super::Executor::new(|_, runnable| {
+            assert!(super::on_network_thread());
+            runnable.run();
+        });
+        executor.execute(super::Runnable::new(|_| {
+            assert!(super::on_network_thread());
+        }));
+        assert!(!super::on_network_thread());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn it_runs_runnables_on_tokio() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let executor = super::Executor::new_with_tokio(runtime.handle().clone());
+        let (tx, rx) = std::sync::mpsc::channel();
+        executor.execute(super::Runnable::new(move |_| {
+            tx.send(super::on_network_thread()).unwrap();
+        }));
+        let on_network_thread = rx.recv_timeout(std::time::Duration::from_secs(5)).unwrap();
+        assert!(!on_network_thread);
     }
 }
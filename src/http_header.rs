@@ -1,9 +1,9 @@
-use std::ffi::{CStr, CString};
+use std::{borrow::Cow, ffi::CString};
 
 use crate::{
-    Cronet_HttpHeaderPtr, Cronet_HttpHeader_Create, Cronet_HttpHeader_Destroy,
+    string, Cronet_HttpHeaderPtr, Cronet_HttpHeader_Create, Cronet_HttpHeader_Destroy,
     Cronet_HttpHeader_name_get, Cronet_HttpHeader_name_set, Cronet_HttpHeader_value_get,
-    Cronet_HttpHeader_value_set, Destroy,
+    Cronet_HttpHeader_value_set,
 };
 
 pub struct HttpHeader {
@@ -20,31 +20,42 @@ impl HttpHeader {
     }
 
     /// Get the name of this header.
-    pub fn name(&self) -> &'static str {
-        unsafe {
-            let c_name = Cronet_HttpHeader_name_get(self.ptr);
-            let name = std::ffi::CStr::from_ptr(c_name).to_str().unwrap();
-            name
-        }
+    /// Invalid UTF-8 is replaced, see [HttpHeader::name_bytes] for the raw name.
+    pub fn name(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_HttpHeader_name_get(self.ptr)) }
+    }
+
+    /// Get the raw bytes of the name of this header.
+    pub fn name_bytes(&self) -> &[u8] {
+        unsafe { string::to_bytes(Cronet_HttpHeader_name_get(self.ptr)) }
     }
 
     /// Set the name of this header.
     pub fn set_name(&self, name: &str) {
         unsafe {
-            let c_name = std::ffi::CString::new(name).unwrap();
+            let c_name = CString::new(name).unwrap();
             Cronet_HttpHeader_name_set(self.ptr, c_name.as_ptr());
         }
     }
 
-    pub fn value(&self) -> &'static str {
-        unsafe {
-            let c_value = Cronet_HttpHeader_value_get(self.ptr);
-            let value = CStr::from_ptr(c_value).to_str().unwrap();
-            value
-        }
+    /// Get the value of this header.
+    /// Header values may contain bytes that aren't valid UTF-8, which are replaced.
+    /// See [HttpHeader::value_bytes] for the raw value.
+    pub fn value(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_HttpHeader_value_get(self.ptr)) }
+    }
+
+    /// Get the raw bytes of the value of this header.
+    pub fn value_bytes(&self) -> &[u8] {
+        unsafe { string::to_bytes(Cronet_HttpHeader_value_get(self.ptr)) }
     }
 
     pub fn set_value(&self, value: &str) {
+        self.set_value_bytes(value.as_bytes());
+    }
+
+    /// Set the value of this header from raw bytes, which don't need to be valid UTF-8.
+    pub fn set_value_bytes(&self, value: &[u8]) {
         unsafe {
             let c_value = CString::new(value).unwrap();
             Cronet_HttpHeader_value_set(self.ptr, c_value.as_ptr());
@@ -52,8 +63,8 @@ impl HttpHeader {
     }
 }
 
-impl Destroy for HttpHeader {
-    fn destroy(&self) {
+impl Drop for HttpHeader {
+    fn drop(&mut self) {
         unsafe { Cronet_HttpHeader_Destroy(self.ptr) }
     }
 }
@@ -66,7 +77,6 @@ impl Default for HttpHeader {
 
 #[cfg(test)]
 mod tests {
-    use crate::Destroy;
 
     #[test]
     fn test_http_header() {
@@ -75,6 +85,13 @@ mod tests {
         http_header.set_value("value");
         assert_eq!(http_header.name(), "name");
         assert_eq!(http_header.value(), "value");
-        http_header.destroy();
+    }
+
+    #[test]
+    fn it_keeps_non_utf8_values_as_bytes() {
+        let http_header = super::HttpHeader::new();
+        http_header.set_value_bytes(b"caf\xe9");
+        assert_eq!(http_header.value_bytes(), b"caf\xe9");
+        assert_eq!(http_header.value(), "caf\u{fffd}");
     }
 }
@@ -5,12 +5,13 @@
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 
 mod annotation;
+mod borrowed;
 mod buffer;
 mod buffer_callback;
 #[cfg(feature = "client")]
 pub mod client;
+mod client_context;
 mod date_time;
-mod destroy;
 mod engine;
 mod engine_params;
 mod engine_result;
@@ -22,8 +23,10 @@ mod public_key_pins;
 mod quic_hint;
 mod request_finished_info;
 mod request_finished_info_listener;
+mod request_timings;
 mod runnable;
-mod state;
+mod string;
+mod unwind;
 mod upload_data_provider;
 mod upload_data_sink;
 mod url_request;
@@ -33,10 +36,10 @@ mod url_request_status_listener;
 mod url_response_info;
 
 pub use annotation::*;
+pub use borrowed::*;
 pub use buffer::*;
 pub use buffer_callback::*;
 pub use date_time::*;
-pub use destroy::*;
 pub use engine::*;
 pub use engine_params::*;
 pub use engine_result::*;
@@ -48,6 +51,7 @@ pub use public_key_pins::*;
 pub use quic_hint::*;
 pub use request_finished_info::*;
 pub use request_finished_info_listener::*;
+pub use request_timings::*;
 pub use runnable::*;
 pub use upload_data_provider::*;
 pub use upload_data_sink::*;
@@ -1,5 +1,7 @@
+use std::time::SystemTime;
+
 use crate::{
-    Cronet_MetricsPtr, Cronet_Metrics_Create, Cronet_Metrics_Destroy,
+    Borrowed, Cronet_DateTimePtr, Cronet_MetricsPtr, Cronet_Metrics_Create, Cronet_Metrics_Destroy,
     Cronet_Metrics_connect_end_get, Cronet_Metrics_connect_end_set,
     Cronet_Metrics_connect_start_get, Cronet_Metrics_connect_start_set, Cronet_Metrics_dns_end_get,
     Cronet_Metrics_dns_end_set, Cronet_Metrics_dns_start_get, Cronet_Metrics_dns_start_set,
@@ -13,7 +15,7 @@ use crate::{
     Cronet_Metrics_sending_start_set, Cronet_Metrics_sent_byte_count_get,
     Cronet_Metrics_sent_byte_count_set, Cronet_Metrics_socket_reused_get,
     Cronet_Metrics_socket_reused_set, Cronet_Metrics_ssl_end_get, Cronet_Metrics_ssl_end_set,
-    Cronet_Metrics_ssl_start_get, Cronet_Metrics_ssl_start_set, DateTime, Destroy,
+    Cronet_Metrics_ssl_start_get, Cronet_Metrics_ssl_start_set, DateTime, RequestTimings,
 };
 
 /// Metrics
@@ -42,7 +44,7 @@ use crate::{
 /// event, not when the event actually occurred, with the exception of push
 /// start and end. If a metric is not meaningful or not available, including
 /// cases when a request finished before reaching that stage, start and end
-/// times will be null. If no time was spent blocking on an event, start and end
+/// times will be null (`None`). If no time was spent blocking on an event, start and end
 /// will be the same time.
 ///
 /// Timestamps are recorded using a clock that is guaranteed not to run
@@ -69,95 +71,56 @@ impl Metrics {
         }
     }
 
-    pub fn request_start(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_request_start_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn request_start(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_request_start_get(self.ptr)) }
     }
 
-    pub fn dns_start(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_dns_start_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn dns_start(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_dns_start_get(self.ptr)) }
     }
 
-    pub fn dns_end(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_dns_end_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn dns_end(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_dns_end_get(self.ptr)) }
     }
 
-    pub fn connect_start(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_connect_start_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn connect_start(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_connect_start_get(self.ptr)) }
     }
 
-    pub fn connect_end(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_connect_end_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn connect_end(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_connect_end_get(self.ptr)) }
     }
 
-    pub fn ssl_start(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_ssl_start_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn ssl_start(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_ssl_start_get(self.ptr)) }
     }
 
-    pub fn ssl_end(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_ssl_end_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn ssl_end(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_ssl_end_get(self.ptr)) }
     }
 
-    pub fn sending_start(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_sending_start_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn sending_start(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_sending_start_get(self.ptr)) }
     }
 
-    pub fn sending_end(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_sending_end_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn sending_end(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_sending_end_get(self.ptr)) }
     }
 
-    pub fn push_start(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_push_start_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn push_start(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_push_start_get(self.ptr)) }
     }
 
-    pub fn push_end(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_push_end_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn push_end(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_push_end_get(self.ptr)) }
     }
 
-    pub fn response_start(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_response_start_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn response_start(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_response_start_get(self.ptr)) }
     }
 
-    pub fn response_end(&self) -> DateTime {
-        unsafe {
-            let ptr = Cronet_Metrics_request_end_get(self.ptr);
-            DateTime { ptr }
-        }
+    pub fn response_end(&self) -> Option<Borrowed<'_, DateTime>> {
+        unsafe { date_time(Cronet_Metrics_request_end_get(self.ptr)) }
     }
 
     pub fn socket_reused(&self) -> bool {
@@ -172,79 +135,106 @@ impl Metrics {
         unsafe { Cronet_Metrics_received_byte_count_get(self.ptr) }
     }
 
-    pub fn set_request_start(&self, datetime: DateTime) {
+    /// Copies the metrics into a [RequestTimings], which can outlive the request
+    /// and also provides the durations between events.
+    pub fn snapshot(&self) -> RequestTimings {
+        unsafe {
+            RequestTimings {
+                request_start: time(Cronet_Metrics_request_start_get(self.ptr)),
+                dns_start: time(Cronet_Metrics_dns_start_get(self.ptr)),
+                dns_end: time(Cronet_Metrics_dns_end_get(self.ptr)),
+                connect_start: time(Cronet_Metrics_connect_start_get(self.ptr)),
+                connect_end: time(Cronet_Metrics_connect_end_get(self.ptr)),
+                ssl_start: time(Cronet_Metrics_ssl_start_get(self.ptr)),
+                ssl_end: time(Cronet_Metrics_ssl_end_get(self.ptr)),
+                sending_start: time(Cronet_Metrics_sending_start_get(self.ptr)),
+                sending_end: time(Cronet_Metrics_sending_end_get(self.ptr)),
+                push_start: time(Cronet_Metrics_push_start_get(self.ptr)),
+                push_end: time(Cronet_Metrics_push_end_get(self.ptr)),
+                response_start: time(Cronet_Metrics_response_start_get(self.ptr)),
+                request_end: time(Cronet_Metrics_request_end_get(self.ptr)),
+                socket_reused: self.socket_reused(),
+                sent_byte_count: self.sent_byte_count(),
+                received_byte_count: self.received_byte_count(),
+                ..Default::default()
+            }
+            .with_durations()
+        }
+    }
+
+    pub fn set_request_start(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_request_start_set(self.ptr, datetime.ptr);
         }
     }
 
-    pub fn set_dns_start(&self, datetime: DateTime) {
+    pub fn set_dns_start(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_dns_start_set(self.ptr, datetime.ptr);
         }
     }
 
-    pub fn set_dns_end(&self, datetime: DateTime) {
+    pub fn set_dns_end(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_dns_end_set(self.ptr, datetime.ptr);
         }
     }
 
-    pub fn set_connect_start(&self, datetime: DateTime) {
+    pub fn set_connect_start(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_connect_start_set(self.ptr, datetime.ptr);
         }
     }
 
-    pub fn set_connect_end(&self, datetime: DateTime) {
+    pub fn set_connect_end(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_connect_end_set(self.ptr, datetime.ptr);
         }
     }
 
-    pub fn set_ssl_start(&self, datetime: DateTime) {
+    pub fn set_ssl_start(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_ssl_start_set(self.ptr, datetime.ptr);
         }
     }
 
-    pub fn set_ssl_end(&self, datetime: DateTime) {
+    pub fn set_ssl_end(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_ssl_end_set(self.ptr, datetime.ptr);
         }
     }
 
-    pub fn set_sending_start(&self, datetime: DateTime) {
+    pub fn set_sending_start(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_sending_start_set(self.ptr, datetime.ptr);
         }
     }
 
-    pub fn set_sending_end(&self, datetime: DateTime) {
+    pub fn set_sending_end(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_sending_end_set(self.ptr, datetime.ptr);
         }
     }
 
-    pub fn set_push_start(&self, datetime: DateTime) {
+    pub fn set_push_start(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_push_start_set(self.ptr, datetime.ptr);
         }
     }
 
-    pub fn set_push_end(&self, datetime: DateTime) {
+    pub fn set_push_end(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_push_end_set(self.ptr, datetime.ptr);
         }
     }
 
-    pub fn set_response_start(&self, datetime: DateTime) {
+    pub fn set_response_start(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_response_start_set(self.ptr, datetime.ptr);
         }
     }
 
-    pub fn set_response_end(&self, datetime: DateTime) {
+    pub fn set_response_end(&self, datetime: &DateTime) {
         unsafe {
             Cronet_Metrics_request_end_set(self.ptr, datetime.ptr);
         }
@@ -269,8 +259,26 @@ impl Metrics {
     }
 }
 
-impl Destroy for Metrics {
-    fn destroy(&self) {
+/// Borrows a point in time of the metrics, which is null if it is not available.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a date time that lives for `'a`.
+unsafe fn date_time<'a>(ptr: Cronet_DateTimePtr) -> Option<Borrowed<'a, DateTime>> {
+    (!ptr.is_null()).then(|| Borrowed::new(DateTime { ptr }))
+}
+
+/// Reads a point in time of the metrics, which is null if it is not available.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a live date time.
+unsafe fn time(ptr: Cronet_DateTimePtr) -> Option<SystemTime> {
+    date_time(ptr).map(|date_time| date_time.get())
+}
+
+impl Drop for Metrics {
+    fn drop(&mut self) {
         unsafe { Cronet_Metrics_Destroy(self.ptr) }
     }
 }
@@ -283,9 +291,9 @@ impl Default for Metrics {
 
 #[cfg(test)]
 mod tests {
-    use std::time::SystemTime;
+    use std::time::{Duration, SystemTime};
 
-    use crate::{DateTime, Destroy};
+    use crate::DateTime;
 
     #[test]
     fn test_metrics() {
@@ -293,40 +301,68 @@ mod tests {
         let now = DateTime::new();
         now.set(SystemTime::now());
 
-        metrics.set_request_start(now);
-        metrics.set_dns_start(now);
-        metrics.set_dns_end(now);
-        metrics.set_connect_start(now);
-        metrics.set_connect_end(now);
-        metrics.set_ssl_start(now);
-        metrics.set_ssl_end(now);
-        metrics.set_sending_start(now);
-        metrics.set_sending_end(now);
-        metrics.set_push_start(now);
-        metrics.set_push_end(now);
-        metrics.set_response_start(now);
-        metrics.set_response_end(now);
+        metrics.set_request_start(&now);
+        metrics.set_dns_start(&now);
+        metrics.set_dns_end(&now);
+        metrics.set_connect_start(&now);
+        metrics.set_connect_end(&now);
+        metrics.set_ssl_start(&now);
+        metrics.set_ssl_end(&now);
+        metrics.set_sending_start(&now);
+        metrics.set_sending_end(&now);
+        metrics.set_push_start(&now);
+        metrics.set_push_end(&now);
+        metrics.set_response_start(&now);
+        metrics.set_response_end(&now);
         metrics.set_socket_reused(true);
         metrics.set_sent_byte_count(100);
         metrics.set_received_byte_count(100);
 
-        assert_eq!(metrics.connect_start().millis(), now.millis());
-        assert_eq!(metrics.connect_end().millis(), now.millis());
-        assert_eq!(metrics.ssl_start().millis(), now.millis());
-        assert_eq!(metrics.ssl_end().millis(), now.millis());
-        assert_eq!(metrics.sending_start().millis(), now.millis());
-        assert_eq!(metrics.sending_end().millis(), now.millis());
-        assert_eq!(metrics.push_start().millis(), now.millis());
-        assert_eq!(metrics.push_end().millis(), now.millis());
-        assert_eq!(metrics.response_start().millis(), now.millis());
-        assert_eq!(metrics.response_end().millis(), now.millis());
-        assert_eq!(metrics.socket_reused(), true);
+        assert_eq!(metrics.connect_start().unwrap().millis(), now.millis());
+        assert_eq!(metrics.connect_end().unwrap().millis(), now.millis());
+        assert_eq!(metrics.ssl_start().unwrap().millis(), now.millis());
+        assert_eq!(metrics.ssl_end().unwrap().millis(), now.millis());
+        assert_eq!(metrics.sending_start().unwrap().millis(), now.millis());
+        assert_eq!(metrics.sending_end().unwrap().millis(), now.millis());
+        assert_eq!(metrics.push_start().unwrap().millis(), now.millis());
+        assert_eq!(metrics.push_end().unwrap().millis(), now.millis());
+        assert_eq!(metrics.response_start().unwrap().millis(), now.millis());
+        assert_eq!(metrics.response_end().unwrap().millis(), now.millis());
+        assert!(metrics.socket_reused());
         assert_eq!(metrics.sent_byte_count(), 100);
         assert_eq!(metrics.received_byte_count(), 100);
-        assert_eq!(metrics.request_start().millis(), now.millis());
-        assert_eq!(metrics.dns_start().millis(), now.millis());
-        assert_eq!(metrics.dns_end().millis(), now.millis());
+        assert_eq!(metrics.request_start().unwrap().millis(), now.millis());
+        assert_eq!(metrics.dns_start().unwrap().millis(), now.millis());
+        assert_eq!(metrics.dns_end().unwrap().millis(), now.millis());
+    }
+
+    #[test]
+    fn it_returns_none_for_missing_times() {
+        let metrics = super::Metrics::new();
+        assert!(metrics.request_start().is_none());
+        assert!(metrics.response_end().is_none());
+    }
+
+    #[test]
+    fn it_snapshots_metrics() {
+        let metrics = super::Metrics::new();
+        let start = DateTime::new();
+        start.set_millis(1_000);
+        let end = DateTime::new();
+        end.set_millis(1_250);
+        metrics.set_request_start(&start);
+        metrics.set_response_start(&start);
+        metrics.set_response_end(&end);
+        metrics.set_socket_reused(true);
+        metrics.set_received_byte_count(100);
 
-        metrics.destroy();
+        let timings = metrics.snapshot();
+        assert_eq!(timings.request_start, Some(start.get()));
+        assert_eq!(timings.dns_start, None);
+        assert_eq!(timings.dns, None);
+        assert_eq!(timings.download, Some(Duration::from_millis(250)));
+        assert_eq!(timings.total, Some(Duration::from_millis(250)));
+        assert!(timings.socket_reused);
+        assert_eq!(timings.received_byte_count, 100);
     }
 }
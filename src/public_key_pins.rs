@@ -1,15 +1,16 @@
 use std::{
-    ffi::{CStr, CString},
+    borrow::Cow,
+    ffi::CString,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use crate::{
-    Cronet_PublicKeyPinsPtr, Cronet_PublicKeyPins_Create, Cronet_PublicKeyPins_Destroy,
+    string, Cronet_PublicKeyPinsPtr, Cronet_PublicKeyPins_Create, Cronet_PublicKeyPins_Destroy,
     Cronet_PublicKeyPins_expiration_date_get, Cronet_PublicKeyPins_expiration_date_set,
     Cronet_PublicKeyPins_host_get, Cronet_PublicKeyPins_host_set,
     Cronet_PublicKeyPins_include_subdomains_get, Cronet_PublicKeyPins_include_subdomains_set,
     Cronet_PublicKeyPins_pins_sha256_add, Cronet_PublicKeyPins_pins_sha256_at,
-    Cronet_PublicKeyPins_pins_sha256_clear, Cronet_PublicKeyPins_pins_sha256_size, Destroy,
+    Cronet_PublicKeyPins_pins_sha256_clear, Cronet_PublicKeyPins_pins_sha256_size,
 };
 
 pub struct PublicKeyPins {
@@ -33,12 +34,8 @@ impl PublicKeyPins {
         }
     }
 
-    pub fn host(&self) -> &str {
-        unsafe {
-            let c_str = Cronet_PublicKeyPins_host_get(self.ptr);
-            let host = CStr::from_ptr(c_str).to_str().unwrap();
-            host
-        }
+    pub fn host(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_PublicKeyPins_host_get(self.ptr)) }
     }
 
     /// Add pins.
@@ -58,11 +55,8 @@ impl PublicKeyPins {
         unsafe { Cronet_PublicKeyPins_pins_sha256_size(self.ptr) }
     }
 
-    pub fn at(&self, index: u32) -> &str {
-        unsafe {
-            let c_str = Cronet_PublicKeyPins_pins_sha256_at(self.ptr, index);
-            CStr::from_ptr(c_str).to_str().unwrap()
-        }
+    pub fn at(&self, index: u32) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_PublicKeyPins_pins_sha256_at(self.ptr, index)) }
     }
 
     pub fn clear(&self) {
@@ -105,8 +99,8 @@ impl PublicKeyPins {
     }
 }
 
-impl Destroy for PublicKeyPins {
-    fn destroy(&self) {
+impl Drop for PublicKeyPins {
+    fn drop(&mut self) {
         unsafe { Cronet_PublicKeyPins_Destroy(self.ptr) }
     }
 }
@@ -121,8 +115,6 @@ impl Default for PublicKeyPins {
 mod tests {
     use std::time::UNIX_EPOCH;
 
-    use crate::Destroy;
-
     #[test]
     fn test_public_key_pins() {
         let public_key_pins = super::PublicKeyPins::new();
@@ -138,9 +130,8 @@ mod tests {
             "sha256/AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
         );
         public_key_pins.set_include_subdomains(true);
-        assert_eq!(public_key_pins.include_subdomains(), true);
+        assert!(public_key_pins.include_subdomains());
         public_key_pins.clear();
         assert_eq!(public_key_pins.size(), 0);
-        public_key_pins.destroy()
     }
 }
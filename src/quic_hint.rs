@@ -1,10 +1,10 @@
-use std::ffi::{CStr, CString};
+use std::{borrow::Cow, ffi::CString};
 
 use crate::{
-    Cronet_QuicHintPtr, Cronet_QuicHint_Create, Cronet_QuicHint_Destroy,
+    string, Cronet_QuicHintPtr, Cronet_QuicHint_Create, Cronet_QuicHint_Destroy,
     Cronet_QuicHint_alternate_port_get, Cronet_QuicHint_alternate_port_set,
     Cronet_QuicHint_host_get, Cronet_QuicHint_host_set, Cronet_QuicHint_port_get,
-    Cronet_QuicHint_port_set, Destroy,
+    Cronet_QuicHint_port_set,
 };
 
 pub struct QuicHint {
@@ -28,12 +28,8 @@ impl QuicHint {
         }
     }
 
-    pub fn host(&self) -> &str {
-        unsafe {
-            let c_str = Cronet_QuicHint_host_get(self.ptr);
-            let host = CStr::from_ptr(c_str).to_str().unwrap();
-            host
-        }
+    pub fn host(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_QuicHint_host_get(self.ptr)) }
     }
 
     /// Set the port of the server that supports QUIC.
@@ -59,8 +55,8 @@ impl QuicHint {
     }
 }
 
-impl Destroy for QuicHint {
-    fn destroy(&self) {
+impl Drop for QuicHint {
+    fn drop(&mut self) {
         unsafe { Cronet_QuicHint_Destroy(self.ptr) }
     }
 }
@@ -73,7 +69,6 @@ impl Default for QuicHint {
 
 #[cfg(test)]
 mod tests {
-    use crate::Destroy;
 
     #[test]
     fn test_quic_hint() {
@@ -84,6 +79,5 @@ mod tests {
         assert_eq!(quic_hint.host(), "www.example.com");
         assert_eq!(quic_hint.port(), 443);
         assert_eq!(quic_hint.alternate_port(), 8443);
-        quic_hint.destroy();
     }
 }
@@ -1,11 +1,10 @@
 use crate::{
-    Annotation, Cronet_RequestFinishedInfoPtr, Cronet_RequestFinishedInfo_Create,
+    Annotation, Borrowed, Cronet_RequestFinishedInfoPtr, Cronet_RequestFinishedInfo_Create,
     Cronet_RequestFinishedInfo_Destroy, Cronet_RequestFinishedInfo_annotations_add,
     Cronet_RequestFinishedInfo_annotations_at, Cronet_RequestFinishedInfo_annotations_clear,
     Cronet_RequestFinishedInfo_annotations_size, Cronet_RequestFinishedInfo_finished_reason_get,
     Cronet_RequestFinishedInfo_finished_reason_set, Cronet_RequestFinishedInfo_metrics_get,
-    Cronet_RequestFinishedInfo_metrics_move, Cronet_RequestFinishedInfo_metrics_set, Destroy,
-    Metrics,
+    Cronet_RequestFinishedInfo_metrics_move, Cronet_RequestFinishedInfo_metrics_set, Metrics,
 };
 
 pub struct RequestFinishedInfo {
@@ -22,21 +21,22 @@ impl RequestFinishedInfo {
     }
 
     /// Set the [Metrics] collected for this request.
-    pub fn set_metrics(&self, metrics: Metrics) {
+    pub fn set_metrics(&self, metrics: &Metrics) {
         unsafe {
             Cronet_RequestFinishedInfo_metrics_set(self.ptr, metrics.ptr);
         }
     }
 
+    /// Moves the values of `metrics` into this request's [Metrics].
     pub fn move_metrics(&self, metrics: Metrics) {
         unsafe { Cronet_RequestFinishedInfo_metrics_move(self.ptr, metrics.ptr) }
     }
 
     /// Get the [Metrics] collected for this request.
-    pub fn metrics(&self) -> Metrics {
+    pub fn metrics(&self) -> Borrowed<'_, Metrics> {
         unsafe {
             let ptr = Cronet_RequestFinishedInfo_metrics_get(self.ptr);
-            Metrics { ptr }
+            Borrowed::new(Metrics { ptr })
         }
     }
 
@@ -81,8 +81,8 @@ impl RequestFinishedInfo {
     }
 }
 
-impl Destroy for RequestFinishedInfo {
-    fn destroy(&self) {
+impl Drop for RequestFinishedInfo {
+    fn drop(&mut self) {
         unsafe { Cronet_RequestFinishedInfo_Destroy(self.ptr) }
     }
 }
@@ -121,24 +121,23 @@ impl TryFrom<u32> for RequestFinishedInfoReason {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Destroy, RequestFinishedInfoReason};
+    use crate::RequestFinishedInfoReason;
 
     #[test]
     fn it_gets_metrics() {
         let url_request_finished_info = super::RequestFinishedInfo::new();
         let metrics = super::Metrics::new();
         metrics.set_received_byte_count(100);
-        url_request_finished_info.set_metrics(metrics);
+        url_request_finished_info.set_metrics(&metrics);
         let metrics2 = url_request_finished_info.metrics();
         assert_eq!(metrics2.received_byte_count(), 100);
-        url_request_finished_info.destroy();
     }
 
     #[test]
     fn it_gets_annotations() {
         let url_request_finished_info = super::RequestFinishedInfo::new();
         let annotation = super::Annotation::default();
-        let annotation_ptr = annotation.ptr.clone();
+        let annotation_ptr = annotation.ptr;
         url_request_finished_info.add_annotation(annotation);
         let annotations_size = url_request_finished_info.annotations_size();
         assert_eq!(annotations_size, 1);
@@ -147,7 +146,6 @@ mod tests {
         url_request_finished_info.clear_annotations();
         let annotations_size = url_request_finished_info.annotations_size();
         assert_eq!(annotations_size, 0);
-        url_request_finished_info.destroy();
     }
 
     #[test]
@@ -156,6 +154,5 @@ mod tests {
         url_request_finished_info.set_finished_reason(super::RequestFinishedInfoReason::Canceled);
         let finished_reason = url_request_finished_info.finished_reason();
         assert_eq!(finished_reason, RequestFinishedInfoReason::Canceled);
-        url_request_finished_info.destroy();
     }
 }
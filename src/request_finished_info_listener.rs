@@ -1,16 +1,15 @@
-use once_cell::sync::Lazy;
+use std::sync::Mutex;
 
 use crate::{
-    state::CronetCallbacks, CronetError, Cronet_ErrorPtr, Cronet_RawDataPtr,
+    client_context, unwind, Borrowed, CronetError, Cronet_ErrorPtr,
     Cronet_RequestFinishedInfoListenerPtr, Cronet_RequestFinishedInfoListener_CreateWith,
     Cronet_RequestFinishedInfoListener_Destroy,
+    Cronet_RequestFinishedInfoListener_GetClientContext,
     Cronet_RequestFinishedInfoListener_SetClientContext, Cronet_RequestFinishedInfoPtr,
-    Cronet_UrlResponseInfoPtr, Destroy, RequestFinishedInfo, UrlResponseInfo,
+    Cronet_UrlResponseInfoPtr, RequestFinishedInfo, UrlResponseInfo,
 };
 
-static mut REQUEST_FINISHED_INFO_LISTENER_CALLBACKS: Lazy<
-    CronetCallbacks<Cronet_RequestFinishedInfoListenerPtr, OnRequestFinishedFn>,
-> = Lazy::new(CronetCallbacks::new);
+type RequestFinishedInfoListenerHandler = Mutex<Box<OnRequestFinishedFn>>;
 
 #[no_mangle]
 unsafe extern "C" fn cronetOnRequestFinished(
@@ -19,73 +18,79 @@ unsafe extern "C" fn cronetOnRequestFinished(
     response_info: Cronet_UrlResponseInfoPtr,
     error: Cronet_ErrorPtr,
 ) {
-    let lockedMap = REQUEST_FINISHED_INFO_LISTENER_CALLBACKS
-        .map()
-        .lock()
-        .unwrap();
-    if let Some(callback) = lockedMap.get(&selfPtr) {
-        callback(
-            RequestFinishedInfoListener { ptr: selfPtr },
-            RequestFinishedInfo { ptr: request_info },
-            UrlResponseInfo { ptr: response_info },
-            CronetError { ptr: error },
-        );
+    let callback = client_context::get::<RequestFinishedInfoListenerHandler>(
+        Cronet_RequestFinishedInfoListener_GetClientContext(selfPtr),
+    );
+    if let Some(callback) = callback {
+        // The request has already finished, so a panic is ignored.
+        let _ = unwind::catch(|| {
+            let mut callback = unwind::lock(&callback);
+            callback(
+                Borrowed::new(RequestFinishedInfoListener { ptr: selfPtr }),
+                Borrowed::new(RequestFinishedInfo { ptr: request_info }),
+                // Either may be null, depending on how the request finished.
+                (!response_info.is_null())
+                    .then(|| Borrowed::new(UrlResponseInfo { ptr: response_info })),
+                (!error.is_null()).then(|| Borrowed::new(CronetError { ptr: error })),
+            );
+        });
     }
 }
 
+/// Listens for finished requests, receiving their [RequestFinishedInfo],
+/// the [UrlResponseInfo] if a response was received, and the [CronetError] if the request failed.
 pub struct RequestFinishedInfoListener {
     pub(crate) ptr: Cronet_RequestFinishedInfoListenerPtr,
 }
 
 impl RequestFinishedInfoListener {
-    pub fn new(on_request_finished: OnRequestFinishedFn) -> Self {
+    pub fn new<F>(on_request_finished: F) -> Self
+    where
+        F: FnMut(
+                Borrowed<'_, RequestFinishedInfoListener>,
+                Borrowed<'_, RequestFinishedInfo>,
+                Option<Borrowed<'_, UrlResponseInfo>>,
+                Option<Borrowed<'_, CronetError>>,
+            ) + Send
+            + 'static,
+    {
         unsafe {
             let ptr = Cronet_RequestFinishedInfoListener_CreateWith(Some(cronetOnRequestFinished));
-            REQUEST_FINISHED_INFO_LISTENER_CALLBACKS
-                .map()
-                .lock()
-                .unwrap()
-                .insert(ptr, on_request_finished);
+            let handler: RequestFinishedInfoListenerHandler =
+                Mutex::new(Box::new(on_request_finished));
+            Cronet_RequestFinishedInfoListener_SetClientContext(
+                ptr,
+                client_context::into_raw(handler),
+            );
             Self { ptr }
         }
     }
-
-    #[allow(clippy::missing_safety_doc)]
-    pub unsafe fn set_client_context(&self, raw_data: Cronet_RawDataPtr) {
-        Cronet_RequestFinishedInfoListener_SetClientContext(self.ptr, raw_data);
-    }
 }
 
-impl Destroy for RequestFinishedInfoListener {
-    fn destroy(&self) {
+impl Drop for RequestFinishedInfoListener {
+    fn drop(&mut self) {
         unsafe {
-            let mut lockedMap = REQUEST_FINISHED_INFO_LISTENER_CALLBACKS
-                .map()
-                .lock()
-                .unwrap();
-            lockedMap.remove(&self.ptr);
+            client_context::release::<RequestFinishedInfoListenerHandler>(
+                Cronet_RequestFinishedInfoListener_GetClientContext(self.ptr),
+            );
             Cronet_RequestFinishedInfoListener_Destroy(self.ptr);
         }
     }
 }
 
-pub type OnRequestFinishedFn = fn(
-    listener: RequestFinishedInfoListener,
-    request_info: RequestFinishedInfo,
-    response_info: UrlResponseInfo,
-    error: CronetError,
-);
+pub type OnRequestFinishedFn = dyn FnMut(
+        Borrowed<'_, RequestFinishedInfoListener>,
+        Borrowed<'_, RequestFinishedInfo>,
+        Option<Borrowed<'_, UrlResponseInfo>>,
+        Option<Borrowed<'_, CronetError>>,
+    ) + Send;
 
 #[cfg(test)]
 mod tests {
-    use crate::Destroy;
 
     #[test]
-    fn it_sets_client_context() {
+    fn it_creates_and_destroys_listener() {
         let listener = super::RequestFinishedInfoListener::new(|_, _, _, _| {});
-        unsafe {
-            listener.set_client_context(std::ptr::null_mut());
-        }
-        listener.destroy();
+        drop(listener);
     }
 }
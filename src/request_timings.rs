@@ -0,0 +1,95 @@
+use std::time::{Duration, SystemTime};
+
+/// An owned snapshot of the [crate::Metrics] of a request, see [crate::Metrics::snapshot].
+///
+/// Points in time that are not available, e.g. because the request finished before reaching that stage,
+/// are `None`, as are the durations derived from them.
+/// Serializable with serde if the `serde` feature is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestTimings {
+    pub request_start: Option<SystemTime>,
+    pub dns_start: Option<SystemTime>,
+    pub dns_end: Option<SystemTime>,
+    pub connect_start: Option<SystemTime>,
+    pub connect_end: Option<SystemTime>,
+    pub ssl_start: Option<SystemTime>,
+    pub ssl_end: Option<SystemTime>,
+    pub sending_start: Option<SystemTime>,
+    pub sending_end: Option<SystemTime>,
+    pub push_start: Option<SystemTime>,
+    pub push_end: Option<SystemTime>,
+    pub response_start: Option<SystemTime>,
+    pub request_end: Option<SystemTime>,
+    /// Time spent resolving the host, from `dns_start` to `dns_end`.
+    pub dns: Option<Duration>,
+    /// Time spent establishing the connection, including TLS, from `connect_start` to `connect_end`.
+    pub connect: Option<Duration>,
+    /// Time spent on the TLS handshake, from `ssl_start` to `ssl_end`.
+    pub tls: Option<Duration>,
+    /// Time spent sending the request, from `sending_start` to `sending_end`.
+    pub send: Option<Duration>,
+    /// Time until the response headers were received, from `request_start` to `response_start`.
+    pub time_to_first_byte: Option<Duration>,
+    /// Time spent receiving the response, from `response_start` to `request_end`.
+    pub download: Option<Duration>,
+    /// Time spent on the whole request, from `request_start` to `request_end`.
+    pub total: Option<Duration>,
+    /// Whether the request reused a socket of an earlier request, in which case DNS and connect times are `None`.
+    pub socket_reused: bool,
+    /// Total bytes sent over the network, including headers.
+    pub sent_byte_count: i64,
+    /// Total bytes received over the network, including headers.
+    pub received_byte_count: i64,
+}
+
+impl RequestTimings {
+    /// Fills in the derived durations from the points in time.
+    pub(crate) fn with_durations(mut self) -> Self {
+        self.dns = between(self.dns_start, self.dns_end);
+        self.connect = between(self.connect_start, self.connect_end);
+        self.tls = between(self.ssl_start, self.ssl_end);
+        self.send = between(self.sending_start, self.sending_end);
+        self.time_to_first_byte = between(self.request_start, self.response_start);
+        self.download = between(self.response_start, self.request_end);
+        self.total = between(self.request_start, self.request_end);
+        self
+    }
+}
+
+/// Returns the time between `start` and `end`, if both are known and in order.
+fn between(start: Option<SystemTime>, end: Option<SystemTime>) -> Option<Duration> {
+    end?.duration_since(start?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::RequestTimings;
+
+    #[test]
+    fn it_derives_durations() {
+        let start = SystemTime::UNIX_EPOCH;
+        let at = |millis| Some(start + Duration::from_millis(millis));
+        let timings = RequestTimings {
+            request_start: at(0),
+            dns_start: at(1),
+            dns_end: at(3),
+            sending_start: at(10),
+            sending_end: at(12),
+            response_start: at(20),
+            request_end: at(50),
+            ..Default::default()
+        }
+        .with_durations();
+
+        assert_eq!(timings.dns, Some(Duration::from_millis(2)));
+        assert_eq!(timings.connect, None);
+        assert_eq!(timings.tls, None);
+        assert_eq!(timings.send, Some(Duration::from_millis(2)));
+        assert_eq!(timings.time_to_first_byte, Some(Duration::from_millis(20)));
+        assert_eq!(timings.download, Some(Duration::from_millis(30)));
+        assert_eq!(timings.total, Some(Duration::from_millis(50)));
+    }
+}
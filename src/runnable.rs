@@ -1,22 +1,27 @@
-use once_cell::sync::Lazy;
+use std::{mem, sync::Mutex};
 
 use crate::{
-    state::CronetCallbacks, Cronet_RunnablePtr, Cronet_Runnable_CreateWith,
-    Cronet_Runnable_Destroy, Cronet_Runnable_Run, Destroy,
+    client_context, unwind, Borrowed, Cronet_RunnablePtr, Cronet_Runnable_CreateWith,
+    Cronet_Runnable_Destroy, Cronet_Runnable_GetClientContext, Cronet_Runnable_Run,
+    Cronet_Runnable_SetClientContext,
 };
 
-static mut RUNNABLE_CALLBACKS: Lazy<CronetCallbacks<Cronet_RunnablePtr, RunnableRunFn>> =
-    Lazy::new(CronetCallbacks::new);
+type RunnableHandler = Mutex<Box<RunnableRunFn>>;
 
 #[no_mangle]
 unsafe extern "C" fn cronetRunnableOnRun(selfPtr: Cronet_RunnablePtr) {
-    let lockedMap = RUNNABLE_CALLBACKS.map().lock().unwrap();
-    if let Some(callback) = lockedMap.get(&selfPtr) {
-        callback(Runnable { ptr: selfPtr });
+    let callback =
+        client_context::get::<RunnableHandler>(Cronet_Runnable_GetClientContext(selfPtr));
+    if let Some(callback) = callback {
+        // A panic must not unwind into the executor that runs the runnable, which may be Cronet's.
+        let _ = unwind::catch(|| {
+            let mut callback = unwind::lock(&callback);
+            callback(Borrowed::new(Runnable { ptr: selfPtr }));
+        });
     }
 }
 
-pub type RunnableRunFn = fn(runnable: Runnable);
+pub type RunnableRunFn = dyn FnMut(Borrowed<'_, Runnable>) + Send;
 
 /// An interface to run commands on the Executor.
 ///
@@ -29,11 +34,15 @@ pub struct Runnable {
 }
 
 impl Runnable {
-    pub fn new(on_run: RunnableRunFn) -> Self {
+    pub fn new<F>(on_run: F) -> Self
+    where
+        F: FnMut(Borrowed<'_, Runnable>) + Send + 'static,
+    {
         unsafe {
             let ptr: *mut crate::Cronet_Runnable =
                 Cronet_Runnable_CreateWith(Some(cronetRunnableOnRun));
-            RUNNABLE_CALLBACKS.map().lock().unwrap().insert(ptr, on_run);
+            let handler: RunnableHandler = Mutex::new(Box::new(on_run));
+            Cronet_Runnable_SetClientContext(ptr, client_context::into_raw(handler));
             Self { ptr }
         }
     }
@@ -43,13 +52,19 @@ impl Runnable {
             Cronet_Runnable_Run(self.ptr);
         }
     }
+
+    /// Gives up ownership of the native runnable, e.g. to an [crate::Executor].
+    pub(crate) fn into_raw(self) -> Cronet_RunnablePtr {
+        let ptr = self.ptr;
+        mem::forget(self);
+        ptr
+    }
 }
 
-impl Destroy for Runnable {
-    fn destroy(&self) {
+impl Drop for Runnable {
+    fn drop(&mut self) {
         unsafe {
-            let mut lockedMap = RUNNABLE_CALLBACKS.map().lock().unwrap();
-            lockedMap.remove(&self.ptr);
+            client_context::release::<RunnableHandler>(Cronet_Runnable_GetClientContext(self.ptr));
             Cronet_Runnable_Destroy(self.ptr)
         }
     }
@@ -59,13 +74,11 @@ unsafe impl Send for Runnable {}
 
 #[cfg(test)]
 mod tests {
-    use crate::Destroy;
 
     #[test]
     fn test_runnable() {
         let runnable = super::Runnable::new(|_| {});
         // TODO: test if the runnable actually executes the callback
         runnable.run();
-        runnable.destroy();
     }
 }
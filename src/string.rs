@@ -0,0 +1,49 @@
+use std::{borrow::Cow, ffi::CStr};
+
+use crate::Cronet_String;
+
+/// Returns the bytes of a string that is owned by a native object, without the trailing nul.
+/// A null pointer is read as an empty string.
+///
+/// # Safety
+///
+/// The string must stay alive and unchanged for the lifetime `'a`,
+/// which callers tie to the native object that owns it.
+pub(crate) unsafe fn to_bytes<'a>(ptr: Cronet_String) -> &'a [u8] {
+    if ptr.is_null() {
+        return &[];
+    }
+    CStr::from_ptr(ptr).to_bytes()
+}
+
+/// Returns a string that is owned by a native object.
+/// Invalid UTF-8 sequences are replaced with `U+FFFD`, in which case the string is copied.
+///
+/// # Safety
+///
+/// See [to_bytes].
+pub(crate) unsafe fn to_str<'a>(ptr: Cronet_String) -> Cow<'a, str> {
+    String::from_utf8_lossy(to_bytes(ptr))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, ptr};
+
+    use super::{to_bytes, to_str};
+
+    #[test]
+    fn it_reads_native_strings() {
+        let valid = b"cronet\0";
+        let invalid = b"caf\xe9\0";
+        unsafe {
+            assert!(matches!(
+                to_str(valid.as_ptr().cast()),
+                Cow::Borrowed("cronet")
+            ));
+            assert_eq!(to_str(invalid.as_ptr().cast()), "caf\u{fffd}");
+            assert_eq!(to_bytes(invalid.as_ptr().cast()), b"caf\xe9");
+            assert_eq!(to_str(ptr::null()), "");
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// Runs `f` and catches a panic, which must not unwind into Cronet.
+/// Returns the panic message if `f` panicked.
+pub(crate) fn catch<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(message)
+}
+
+/// Returns the message of a panic payload, which is a string unless the panic was raised with a custom payload.
+pub(crate) fn message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => (*message).to_owned(),
+            Err(_) => String::from("Box<dyn Any>"),
+        },
+    }
+}
+
+/// Locks a handler, even if an earlier callback panicked while holding the lock.
+pub(crate) fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{panic, sync::Mutex};
+
+    use super::{catch, lock};
+
+    #[test]
+    fn it_catches_panics() {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let formatted = catch(|| panic!("handler {}", 1));
+        let literal = catch(|| panic!("handler"));
+        let custom = catch(|| panic::panic_any(1));
+        panic::set_hook(hook);
+
+        assert_eq!(catch(|| 1), Ok(1));
+        assert_eq!(formatted, Err(String::from("handler 1")));
+        assert_eq!(literal, Err(String::from("handler")));
+        assert_eq!(custom, Err(String::from("Box<dyn Any>")));
+    }
+
+    #[test]
+    fn it_locks_poisoned_handlers() {
+        let mutex = Mutex::new(1);
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let _ = catch(|| {
+            let _guard = mutex.lock().unwrap();
+            panic!("poison");
+        });
+        panic::set_hook(hook);
+
+        assert!(mutex.is_poisoned());
+        assert_eq!(*lock(&mutex), 1);
+    }
+}
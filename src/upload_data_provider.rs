@@ -1,25 +1,45 @@
-use once_cell::sync::Lazy;
+use std::{mem, sync::Mutex};
 
 use crate::{
-    state::CronetCallbacks, Buffer, Cronet_BufferPtr, Cronet_ClientContext,
-    Cronet_UploadDataProviderPtr, Cronet_UploadDataProvider_Close,
-    Cronet_UploadDataProvider_CreateWith, Cronet_UploadDataProvider_Destroy,
-    Cronet_UploadDataProvider_GetClientContext, Cronet_UploadDataProvider_GetLength,
-    Cronet_UploadDataProvider_Read, Cronet_UploadDataProvider_Rewind,
-    Cronet_UploadDataProvider_SetClientContext, Cronet_UploadDataSinkPtr, Destroy, UploadDataSink,
+    client_context, unwind, Borrowed, Buffer, Cronet_BufferPtr, Cronet_UploadDataProviderPtr,
+    Cronet_UploadDataProvider_Close, Cronet_UploadDataProvider_CreateWith,
+    Cronet_UploadDataProvider_Destroy, Cronet_UploadDataProvider_GetClientContext,
+    Cronet_UploadDataProvider_GetLength, Cronet_UploadDataProvider_Read,
+    Cronet_UploadDataProvider_Rewind, Cronet_UploadDataProvider_SetClientContext,
+    Cronet_UploadDataSinkPtr, UploadDataSink,
 };
 
-static mut UPLOAD_DATA_PROVIDER_CALLBACKS: Lazy<
-    CronetCallbacks<Cronet_UploadDataProviderPtr, Box<dyn UploadDataProviderHandler>>,
-> = Lazy::new(CronetCallbacks::new);
+type UploadDataProviderHandlerBox = Mutex<Box<dyn UploadDataProviderHandler>>;
+
+/// Invokes `f` with the handler of the provider and returns its result,
+/// or an error message if the handler panicked, either now or in an earlier call.
+///
+/// A handler that panicked may be in an inconsistent state, so it is not invoked again.
+unsafe fn dispatch<T>(
+    self_ptr: Cronet_UploadDataProviderPtr,
+    f: impl FnOnce(&mut dyn UploadDataProviderHandler) -> T,
+) -> Result<T, String> {
+    let handler = client_context::get::<UploadDataProviderHandlerBox>(
+        Cronet_UploadDataProvider_GetClientContext(self_ptr),
+    )
+    .ok_or_else(|| String::from("The upload data provider was dropped"))?;
+    unwind::catch(|| {
+        let mut callback = handler
+            .lock()
+            .map_err(|_| String::from("The upload data provider panicked earlier"))?;
+        Ok(f(callback.as_mut()))
+    })?
+}
 
 #[no_mangle]
 unsafe extern "C" fn cronetUploadDataProviderGetLength(
     selfPtr: Cronet_UploadDataProviderPtr,
 ) -> i64 {
-    let lockedMap = UPLOAD_DATA_PROVIDER_CALLBACKS.map().lock().unwrap();
-    let callback = lockedMap.get(&selfPtr).unwrap();
-    callback.length(UploadDataProvider { ptr: selfPtr })
+    // On failure, the upload is treated as chunked, so that the error is reported by the first read.
+    dispatch(selfPtr, |callback| {
+        callback.length(Borrowed::new(UploadDataProvider { ptr: selfPtr }))
+    })
+    .unwrap_or(-1)
 }
 
 #[no_mangle]
@@ -28,15 +48,21 @@ unsafe extern "C" fn cronetUploadDataProviderRead(
     upload_data_sink_ptr: Cronet_UploadDataSinkPtr,
     buffer_ptr: Cronet_BufferPtr,
 ) {
-    let lockedMap = UPLOAD_DATA_PROVIDER_CALLBACKS.map().lock().unwrap();
-    let callback = lockedMap.get(&self_ptr).unwrap();
-    callback.read(
-        UploadDataProvider { ptr: self_ptr },
-        UploadDataSink {
+    let result = dispatch(self_ptr, |callback| {
+        callback.read(
+            Borrowed::new(UploadDataProvider { ptr: self_ptr }),
+            Borrowed::new(UploadDataSink {
+                ptr: upload_data_sink_ptr,
+            }),
+            Borrowed::new(Buffer { ptr: buffer_ptr }),
+        );
+    });
+    if let Err(message) = result {
+        let sink = Borrowed::new(UploadDataSink {
             ptr: upload_data_sink_ptr,
-        },
-        Buffer { ptr: buffer_ptr },
-    );
+        });
+        sink.on_read_error(&message);
+    }
 }
 
 #[no_mangle]
@@ -44,21 +70,37 @@ unsafe extern "C" fn cronetUploadDataProviderRewind(
     self_ptr: Cronet_UploadDataProviderPtr,
     upload_data_sink_ptr: Cronet_UploadDataSinkPtr,
 ) {
-    let mut lockedMap = UPLOAD_DATA_PROVIDER_CALLBACKS.map().lock().unwrap();
-    let callback = lockedMap.get_mut(&self_ptr).unwrap();
-    callback.rewind(
-        UploadDataProvider { ptr: self_ptr },
-        UploadDataSink {
+    let result = dispatch(self_ptr, |callback| {
+        callback.rewind(
+            Borrowed::new(UploadDataProvider { ptr: self_ptr }),
+            Borrowed::new(UploadDataSink {
+                ptr: upload_data_sink_ptr,
+            }),
+        );
+    });
+    if let Err(message) = result {
+        let sink = Borrowed::new(UploadDataSink {
             ptr: upload_data_sink_ptr,
-        },
-    );
+        });
+        sink.on_rewind_error(&message);
+    }
 }
 
 #[no_mangle]
 unsafe extern "C" fn cronetUploadDataProviderClose(selfPtr: Cronet_UploadDataProviderPtr) {
-    let lockedMap = UPLOAD_DATA_PROVIDER_CALLBACKS.map().lock().unwrap();
-    let callback = lockedMap.get(&selfPtr).unwrap();
-    callback.close(UploadDataProvider { ptr: selfPtr });
+    // The provider was handed over by `UrlRequestParams::set_upload_data_provider`
+    // and is no longer used once it has been closed.
+    let provider = UploadDataProvider { ptr: selfPtr };
+    let handler = client_context::get::<UploadDataProviderHandlerBox>(
+        Cronet_UploadDataProvider_GetClientContext(selfPtr),
+    );
+    if let Some(handler) = handler {
+        // Resources are released even if the handler panicked earlier. A panic while closing is ignored.
+        let _ = unwind::catch(|| {
+            unwind::lock(&handler).close(Borrowed::new(UploadDataProvider { ptr: selfPtr }));
+        });
+    }
+    drop(provider);
 }
 
 pub struct UploadDataProvider {
@@ -74,11 +116,8 @@ impl UploadDataProvider {
                 Some(cronetUploadDataProviderRewind),
                 Some(cronetUploadDataProviderClose),
             );
-            UPLOAD_DATA_PROVIDER_CALLBACKS
-                .map()
-                .lock()
-                .unwrap()
-                .insert(ptr, Box::new(handler));
+            let handler: UploadDataProviderHandlerBox = Mutex::new(Box::new(handler));
+            Cronet_UploadDataProvider_SetClientContext(ptr, client_context::into_raw(handler));
             Self { ptr }
         }
     }
@@ -87,13 +126,13 @@ impl UploadDataProvider {
         unsafe { Cronet_UploadDataProvider_GetLength(self.ptr) }
     }
 
-    pub fn read(&self, upload_data_sink: UploadDataSink, buffer: Buffer) {
+    pub fn read(&self, upload_data_sink: &UploadDataSink, buffer: &Buffer) {
         unsafe {
             Cronet_UploadDataProvider_Read(self.ptr, upload_data_sink.ptr, buffer.ptr);
         }
     }
 
-    pub fn rewind(&self, upload_data_sink: UploadDataSink) {
+    pub fn rewind(&self, upload_data_sink: &UploadDataSink) {
         unsafe {
             Cronet_UploadDataProvider_Rewind(self.ptr, upload_data_sink.ptr);
         }
@@ -105,26 +144,31 @@ impl UploadDataProvider {
         }
     }
 
-    #[allow(clippy::missing_safety_doc)]
-    pub unsafe fn set_client_context(&self, client_context: Cronet_ClientContext) {
-        Cronet_UploadDataProvider_SetClientContext(self.ptr, client_context)
-    }
-
-    pub fn client_context(&self) -> Cronet_ClientContext {
-        unsafe { Cronet_UploadDataProvider_GetClientContext(self.ptr) }
+    /// Gives up ownership of the native provider, e.g. to [crate::UrlRequestParams].
+    pub(crate) fn into_raw(self) -> Cronet_UploadDataProviderPtr {
+        let ptr = self.ptr;
+        mem::forget(self);
+        ptr
     }
 }
 
-impl Destroy for UploadDataProvider {
-    fn destroy(&self) {
-        unsafe { Cronet_UploadDataProvider_Destroy(self.ptr) }
+impl Drop for UploadDataProvider {
+    fn drop(&mut self) {
+        unsafe {
+            client_context::release::<UploadDataProviderHandlerBox>(
+                Cronet_UploadDataProvider_GetClientContext(self.ptr),
+            );
+            Cronet_UploadDataProvider_Destroy(self.ptr)
+        }
     }
 }
 
+/// If a method panics, the pending read or rewind fails with the panic message.
+/// The handler is not invoked again afterwards, except for `close`.
 pub trait UploadDataProviderHandler {
     /// If this is a non-chunked upload, returns the length of the upload.
     /// Must always return -1 if this is a chunked upload.
-    fn length(&self, upload_data_provider: UploadDataProvider) -> i64;
+    fn length(&self, upload_data_provider: Borrowed<'_, UploadDataProvider>) -> i64;
 
     /// Reads upload data into `buffer`. Each call of this method must be followed by a
     /// single call, either synchronous or asynchronous, to
@@ -138,7 +182,12 @@ pub trait UploadDataProviderHandler {
     ///
     /// * `sink`: The object to notify when the read has completed, successfully or otherwise.
     /// * `buffer`: The buffer to copy the read bytes into.
-    fn read(&self, upload_data_provider: UploadDataProvider, sink: UploadDataSink, buffer: Buffer);
+    fn read(
+        &mut self,
+        upload_data_provider: Borrowed<'_, UploadDataProvider>,
+        sink: Borrowed<'_, UploadDataSink>,
+        buffer: Borrowed<'_, Buffer>,
+    );
 
     /// Rewinds upload data. Each call must be followed by a single
     /// call, either synchronous or asynchronous, to
@@ -156,38 +205,99 @@ pub trait UploadDataProviderHandler {
     /// Arguments:
     ///
     /// * `sink`: The object to notify when the rewind operation has completed, successfully or otherwise.
-    fn rewind(&mut self, upload_data_provider: UploadDataProvider, sink: UploadDataSink);
+    fn rewind(
+        &mut self,
+        upload_data_provider: Borrowed<'_, UploadDataProvider>,
+        sink: Borrowed<'_, UploadDataSink>,
+    );
 
     /// Called when this [UploadDataProvider] is no longer needed by a request, so that resources (like a file) can be explicitly released.
-    fn close(&self, upload_data_provider: UploadDataProvider);
+    /// The provider is dropped right after this method returns.
+    fn close(&self, upload_data_provider: Borrowed<'_, UploadDataProvider>);
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Mutex;
+
     use crate::{
-        Buffer, Destroy, UploadDataProvider, UploadDataProviderHandler, UploadDataSink,
+        Borrowed, Buffer, UploadDataProvider, UploadDataProviderHandler, UploadDataSink,
         UploadDataSinkCallbacks,
     };
 
     struct TestUploadDataProviderHandler;
 
     impl UploadDataProviderHandler for TestUploadDataProviderHandler {
-        fn length(&self, _: UploadDataProvider) -> i64 {
+        fn length(&self, _: Borrowed<'_, UploadDataProvider>) -> i64 {
             10
         }
 
-        fn read(&self, _: UploadDataProvider, sink: UploadDataSink, buffer: Buffer) {
+        fn read(
+            &mut self,
+            _: Borrowed<'_, UploadDataProvider>,
+            sink: Borrowed<'_, UploadDataSink>,
+            buffer: Borrowed<'_, Buffer>,
+        ) {
             let size = buffer.size();
             sink.on_read_succeeded(size, false);
         }
 
-        fn rewind(&mut self, _: UploadDataProvider, sink: UploadDataSink) {
+        fn rewind(
+            &mut self,
+            _: Borrowed<'_, UploadDataProvider>,
+            sink: Borrowed<'_, UploadDataSink>,
+        ) {
             sink.on_rewind_succeeded();
         }
 
-        fn close(&self, upload_data_provider: UploadDataProvider) {
-            upload_data_provider.destroy();
+        fn close(&self, _: Borrowed<'_, UploadDataProvider>) {}
+    }
+
+    struct PanickingUploadDataProviderHandler;
+
+    impl UploadDataProviderHandler for PanickingUploadDataProviderHandler {
+        fn length(&self, _: Borrowed<'_, UploadDataProvider>) -> i64 {
+            10
+        }
+
+        fn read(
+            &mut self,
+            _: Borrowed<'_, UploadDataProvider>,
+            _: Borrowed<'_, UploadDataSink>,
+            _: Borrowed<'_, Buffer>,
+        ) {
+            panic!("read failed");
+        }
+
+        fn rewind(
+            &mut self,
+            _: Borrowed<'_, UploadDataProvider>,
+            sink: Borrowed<'_, UploadDataSink>,
+        ) {
+            sink.on_rewind_succeeded();
         }
+
+        fn close(&self, _: Borrowed<'_, UploadDataProvider>) {}
+    }
+
+    static ERRORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    #[test]
+    fn it_reports_panics_as_read_errors() {
+        let upload_data_provider = UploadDataProvider::new(PanickingUploadDataProviderHandler);
+        let buffer = Buffer::new_with_size(10);
+        let sink = UploadDataSink::new(UploadDataSinkCallbacks {
+            on_read_succeeded: |_, _, _| {},
+            on_read_error: |_, message| ERRORS.lock().unwrap().push(message.to_owned()),
+            on_rewind_succeeded: |_| {},
+            on_rewind_error: |_, message| ERRORS.lock().unwrap().push(message.to_owned()),
+        });
+        upload_data_provider.read(&sink, &buffer);
+        upload_data_provider.rewind(&sink);
+        assert_eq!(
+            *ERRORS.lock().unwrap(),
+            ["read failed", "The upload data provider panicked earlier"]
+        );
     }
 
     #[test]
@@ -203,8 +313,7 @@ mod tests {
             on_rewind_succeeded: |_| {},
             on_rewind_error: |_, _| {},
         };
-        upload_data_provider.read(UploadDataSink::new(callbacks), buffer);
-        upload_data_provider.rewind(UploadDataSink::new(callbacks));
-        upload_data_provider.destroy();
+        upload_data_provider.read(&UploadDataSink::new(callbacks), &buffer);
+        upload_data_provider.rewind(&UploadDataSink::new(callbacks));
     }
 }
@@ -1,17 +1,16 @@
-use std::ffi::{CStr, CString};
-
-use once_cell::sync::Lazy;
+use std::{ffi::CString, sync::Arc};
 
 use crate::{
-    state::CronetCallbacks, Cronet_String, Cronet_UploadDataSinkPtr,
+    client_context, string, unwind, Borrowed, Cronet_String, Cronet_UploadDataSinkPtr,
     Cronet_UploadDataSink_CreateWith, Cronet_UploadDataSink_Destroy,
-    Cronet_UploadDataSink_OnReadError, Cronet_UploadDataSink_OnReadSucceeded,
-    Cronet_UploadDataSink_OnRewindError, Cronet_UploadDataSink_OnRewindSucceeded, Destroy,
+    Cronet_UploadDataSink_GetClientContext, Cronet_UploadDataSink_OnReadError,
+    Cronet_UploadDataSink_OnReadSucceeded, Cronet_UploadDataSink_OnRewindError,
+    Cronet_UploadDataSink_OnRewindSucceeded, Cronet_UploadDataSink_SetClientContext,
 };
 
-static mut UPLOAD_DATA_SINK_CALLBACKS: Lazy<
-    CronetCallbacks<Cronet_UploadDataSinkPtr, UploadDataSinkCallbacks>,
-> = Lazy::new(CronetCallbacks::new);
+unsafe fn callbacks(self_ptr: Cronet_UploadDataSinkPtr) -> Option<Arc<UploadDataSinkCallbacks>> {
+    client_context::get(Cronet_UploadDataSink_GetClientContext(self_ptr))
+}
 
 #[no_mangle]
 unsafe extern "C" fn cronetUploadDataSinkOnReadSucceeded(
@@ -19,10 +18,15 @@ unsafe extern "C" fn cronetUploadDataSinkOnReadSucceeded(
     bytes_read: u64,
     is_final_chunk: bool,
 ) {
-    let lockedMap = UPLOAD_DATA_SINK_CALLBACKS.map().lock().unwrap();
-    if let Some(callback) = lockedMap.get(&selfPtr) {
+    if let Some(callback) = callbacks(selfPtr) {
         let on_read_succeeded = callback.on_read_succeeded;
-        on_read_succeeded(UploadDataSink { ptr: selfPtr }, bytes_read, is_final_chunk);
+        let _ = unwind::catch(|| {
+            on_read_succeeded(
+                Borrowed::new(UploadDataSink { ptr: selfPtr }),
+                bytes_read,
+                is_final_chunk,
+            )
+        });
     }
 }
 
@@ -31,21 +35,24 @@ unsafe extern "C" fn cronetUploadDataSinkOnReadError(
     selfPtr: Cronet_UploadDataSinkPtr,
     error_message: Cronet_String,
 ) {
-    let lockedMap = UPLOAD_DATA_SINK_CALLBACKS.map().lock().unwrap();
-    if let Some(callback) = lockedMap.get(&selfPtr) {
-        let c_str = CStr::from_ptr(error_message);
-        let error_message = c_str.to_str().unwrap();
+    if let Some(callback) = callbacks(selfPtr) {
+        let error_message = string::to_str(error_message);
         let on_read_error = callback.on_read_error;
-        on_read_error(UploadDataSink { ptr: selfPtr }, error_message);
+        let _ = unwind::catch(|| {
+            on_read_error(
+                Borrowed::new(UploadDataSink { ptr: selfPtr }),
+                &error_message,
+            )
+        });
     }
 }
 
 #[no_mangle]
 unsafe extern "C" fn cronetUploadDataSinkOnRewindSucceeded(selfPtr: Cronet_UploadDataSinkPtr) {
-    let lockedMap = UPLOAD_DATA_SINK_CALLBACKS.map().lock().unwrap();
-    if let Some(callback) = lockedMap.get(&selfPtr) {
+    if let Some(callback) = callbacks(selfPtr) {
         let on_rewind_succeeded = callback.on_rewind_succeeded;
-        on_rewind_succeeded(UploadDataSink { ptr: selfPtr });
+        let _ =
+            unwind::catch(|| on_rewind_succeeded(Borrowed::new(UploadDataSink { ptr: selfPtr })));
     }
 }
 
@@ -54,12 +61,15 @@ unsafe extern "C" fn cronetUploadDataSinkOnRewindError(
     selfPtr: Cronet_UploadDataSinkPtr,
     error_message: Cronet_String,
 ) {
-    let lockedMap = UPLOAD_DATA_SINK_CALLBACKS.map().lock().unwrap();
-    if let Some(callback) = lockedMap.get(&selfPtr) {
-        let c_str = CStr::from_ptr(error_message);
-        let error_message = c_str.to_str().unwrap();
+    if let Some(callback) = callbacks(selfPtr) {
+        let error_message = string::to_str(error_message);
         let on_rewind_error = callback.on_rewind_error;
-        on_rewind_error(UploadDataSink { ptr: selfPtr }, error_message);
+        let _ = unwind::catch(|| {
+            on_rewind_error(
+                Borrowed::new(UploadDataSink { ptr: selfPtr }),
+                &error_message,
+            )
+        });
     }
 }
 
@@ -71,10 +81,11 @@ pub struct UploadDataSinkCallbacks {
     pub on_rewind_error: OnRewindErrorFn,
 }
 
-pub type OnReadSucceededFn = fn(callback: UploadDataSink, bytes_read: u64, is_final_chunk: bool);
-pub type OnReadErrorFn = fn(callback: UploadDataSink, error_message: &str);
-pub type OnRewindSucceededFn = fn(callback: UploadDataSink);
-pub type OnRewindErrorFn = fn(callback: UploadDataSink, error_message: &str);
+pub type OnReadSucceededFn =
+    fn(callback: Borrowed<'_, UploadDataSink>, bytes_read: u64, is_final_chunk: bool);
+pub type OnReadErrorFn = fn(callback: Borrowed<'_, UploadDataSink>, error_message: &str);
+pub type OnRewindSucceededFn = fn(callback: Borrowed<'_, UploadDataSink>);
+pub type OnRewindErrorFn = fn(callback: Borrowed<'_, UploadDataSink>, error_message: &str);
 
 /// Defines callbacks methods for [crate::UploadDataProvider].
 /// All methods may be called synchronously or asynchronously, on any thread.
@@ -91,11 +102,7 @@ impl UploadDataSink {
                 Some(cronetUploadDataSinkOnRewindSucceeded),
                 Some(cronetUploadDataSinkOnRewindError),
             );
-            UPLOAD_DATA_SINK_CALLBACKS
-                .map()
-                .lock()
-                .unwrap()
-                .insert(ptr, callbacks);
+            Cronet_UploadDataSink_SetClientContext(ptr, client_context::into_raw(callbacks));
             Self { ptr }
         }
     }
@@ -140,11 +147,12 @@ impl UploadDataSink {
     }
 }
 
-impl Destroy for UploadDataSink {
-    fn destroy(&self) {
+impl Drop for UploadDataSink {
+    fn drop(&mut self) {
         unsafe {
-            let mut lockedMap = UPLOAD_DATA_SINK_CALLBACKS.map().lock().unwrap();
-            lockedMap.remove(&self.ptr);
+            client_context::release::<UploadDataSinkCallbacks>(
+                Cronet_UploadDataSink_GetClientContext(self.ptr),
+            );
             Cronet_UploadDataSink_Destroy(self.ptr);
         }
     }
@@ -152,7 +160,7 @@ impl Destroy for UploadDataSink {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Destroy, UploadDataSink, UploadDataSinkCallbacks};
+    use crate::{UploadDataSink, UploadDataSinkCallbacks};
 
     #[test]
     fn test_upload_data_sink() {
@@ -167,6 +175,5 @@ mod tests {
         upload_data_sink.on_rewind_succeeded();
         upload_data_sink.on_read_error("error");
         upload_data_sink.on_rewind_error("error");
-        upload_data_sink.destroy();
     }
 }
@@ -4,8 +4,8 @@ use crate::{
     Buffer, Cronet_UrlRequestPtr, Cronet_UrlRequest_Cancel, Cronet_UrlRequest_Create,
     Cronet_UrlRequest_Destroy, Cronet_UrlRequest_FollowRedirect, Cronet_UrlRequest_GetStatus,
     Cronet_UrlRequest_InitWithParams, Cronet_UrlRequest_IsDone, Cronet_UrlRequest_Read,
-    Cronet_UrlRequest_Start, Destroy, Engine, EngineResult, Executor, UrlRequestCallback,
-    UrlRequestParams, UrlRequestStatusListener,
+    Cronet_UrlRequest_Start, Engine, EngineResult, Executor, UrlRequestCallback, UrlRequestParams,
+    UrlRequestStatusListener,
 };
 
 /// Controls an HTTP request.
@@ -88,10 +88,10 @@ impl UrlRequest {
     /// Arguments:
     ///
     /// * `buffer`: Buffer to write the response body to.
-    /// The app must not read or modify the buffer's position, limit, or data between its position and limit until the request calls back into the `URLRequestCallbackHandler`.
+    ///   The app must not read or modify the buffer's position, limit, or data between its position and limit until the request calls back into the `URLRequestCallbackHandler`.
     pub fn read(&self, buffer: Buffer) -> EngineResult {
         unsafe {
-            let result = Cronet_UrlRequest_Read(self.ptr, buffer.ptr);
+            let result = Cronet_UrlRequest_Read(self.ptr, buffer.into_raw());
             EngineResult::try_from(result).unwrap()
         }
     }
@@ -115,26 +115,32 @@ impl UrlRequest {
     }
 
     /// Queries the status of the request.
+    /// The listener is destroyed right after it was invoked.
     ///
     /// Arguments:
     ///
     /// * `listener`: A `URLRequestStatusListener` that will be invoked with the request's current status.
-    /// The listener will be invoked back on the [Executor] passed in when the request was created.
+    ///   The listener will be invoked back on the [Executor] passed in when the request was created.
     pub fn status(&self, listener: UrlRequestStatusListener) {
         unsafe {
-            Cronet_UrlRequest_GetStatus(self.ptr, listener.ptr);
+            Cronet_UrlRequest_GetStatus(self.ptr, listener.into_raw());
         }
     }
 }
 
-impl Destroy for UrlRequest {
-    fn destroy(&self) {
+impl Drop for UrlRequest {
+    fn drop(&mut self) {
         unsafe {
             Cronet_UrlRequest_Destroy(self.ptr);
         }
     }
 }
 
+unsafe impl Send for UrlRequest {}
+
+// The methods of a Cronet request may be called from any thread.
+unsafe impl Sync for UrlRequest {}
+
 impl Default for UrlRequest {
     fn default() -> Self {
         Self::new()
@@ -1,17 +1,51 @@
-use std::ffi::CStr;
-
-use once_cell::sync::Lazy;
+use std::sync::Mutex;
 
 use crate::{
-    state::CronetCallbacks, Buffer, CronetError, Cronet_BufferPtr, Cronet_ErrorPtr, Cronet_String,
-    Cronet_UrlRequestCallbackPtr, Cronet_UrlRequestCallback_CreateWith,
-    Cronet_UrlRequestCallback_Destroy, Cronet_UrlRequestPtr, Cronet_UrlResponseInfoPtr, Destroy,
-    UrlRequest, UrlResponseInfo,
+    client_context, string, unwind, Borrowed, Buffer, CronetError, Cronet_BufferPtr,
+    Cronet_ErrorPtr, Cronet_String, Cronet_UrlRequestCallbackPtr,
+    Cronet_UrlRequestCallback_CreateWith, Cronet_UrlRequestCallback_Destroy,
+    Cronet_UrlRequestCallback_GetClientContext, Cronet_UrlRequestCallback_SetClientContext,
+    Cronet_UrlRequestPtr, Cronet_UrlResponseInfoPtr, UrlRequest, UrlResponseInfo,
 };
 
-static mut URL_REQUEST_CALLBACK_CALLBACKS: Lazy<
-    CronetCallbacks<Cronet_UrlRequestCallbackPtr, Box<dyn UrlRequestCallbackHandler>>,
-> = Lazy::new(CronetCallbacks::new);
+type UrlRequestCallbackHandlerBox = Mutex<Box<dyn UrlRequestCallbackHandler>>;
+
+/// Invokes `f` with the handler of the callback.
+///
+/// A panic in the handler doesn't unwind into Cronet, but is reported to
+/// [UrlRequestCallbackHandler::on_panicked] instead. If that panics as well, the request is cancelled.
+unsafe fn dispatch(
+    self_ptr: Cronet_UrlRequestCallbackPtr,
+    request_ptr: Cronet_UrlRequestPtr,
+    f: impl FnOnce(&mut dyn UrlRequestCallbackHandler),
+) {
+    let handler = client_context::get::<UrlRequestCallbackHandlerBox>(
+        Cronet_UrlRequestCallback_GetClientContext(self_ptr),
+    );
+    let Some(handler) = handler else {
+        return;
+    };
+    let Err(message) = unwind::catch(|| f(unwind::lock(&handler).as_mut())) else {
+        return;
+    };
+    let result = unwind::catch(|| {
+        unwind::lock(&handler).on_panicked(
+            Borrowed::new(UrlRequestCallback { ptr: self_ptr }),
+            Borrowed::new(UrlRequest { ptr: request_ptr }),
+            message,
+        );
+    });
+    if result.is_err() {
+        Borrowed::new(UrlRequest { ptr: request_ptr }).cancel();
+    }
+}
+
+/// Borrows the response info of a failed or cancelled request, which is null if no response was received.
+unsafe fn response_info<'a>(
+    info_ptr: Cronet_UrlResponseInfoPtr,
+) -> Option<Borrowed<'a, UrlResponseInfo>> {
+    (!info_ptr.is_null()).then(|| Borrowed::new(UrlResponseInfo { ptr: info_ptr }))
+}
 
 #[no_mangle]
 unsafe extern "C" fn cronetUrlRequestCallbackOnRedirectReceived(
@@ -20,16 +54,15 @@ unsafe extern "C" fn cronetUrlRequestCallbackOnRedirectReceived(
     info_ptr: Cronet_UrlResponseInfoPtr,
     new_location_url: Cronet_String,
 ) {
-    let mut lockedMap = URL_REQUEST_CALLBACK_CALLBACKS.map().lock().unwrap();
-    let callback = lockedMap.get_mut(&self_ptr).unwrap();
-    let c_str = CStr::from_ptr(new_location_url);
-    let new_location_url = c_str.to_str().unwrap();
-    callback.on_redirect_received(
-        UrlRequestCallback { ptr: self_ptr },
-        UrlRequest { ptr: request_ptr },
-        UrlResponseInfo { ptr: info_ptr },
-        new_location_url,
-    );
+    let new_location_url = string::to_str(new_location_url);
+    dispatch(self_ptr, request_ptr, |callback| {
+        callback.on_redirect_received(
+            Borrowed::new(UrlRequestCallback { ptr: self_ptr }),
+            Borrowed::new(UrlRequest { ptr: request_ptr }),
+            Borrowed::new(UrlResponseInfo { ptr: info_ptr }),
+            &new_location_url,
+        );
+    });
 }
 
 #[no_mangle]
@@ -38,13 +71,13 @@ unsafe extern "C" fn cronetUrlRequestCallbackOnResponseStarted(
     request_ptr: Cronet_UrlRequestPtr,
     info_ptr: Cronet_UrlResponseInfoPtr,
 ) {
-    let mut lockedMap = URL_REQUEST_CALLBACK_CALLBACKS.map().lock().unwrap();
-    let callback = lockedMap.get_mut(&self_ptr).unwrap();
-    callback.on_response_started(
-        UrlRequestCallback { ptr: self_ptr },
-        UrlRequest { ptr: request_ptr },
-        UrlResponseInfo { ptr: info_ptr },
-    );
+    dispatch(self_ptr, request_ptr, |callback| {
+        callback.on_response_started(
+            Borrowed::new(UrlRequestCallback { ptr: self_ptr }),
+            Borrowed::new(UrlRequest { ptr: request_ptr }),
+            Borrowed::new(UrlResponseInfo { ptr: info_ptr }),
+        );
+    });
 }
 
 #[no_mangle]
@@ -55,15 +88,16 @@ unsafe extern "C" fn cronetUrlRequestCallbackOnReadCompleted(
     buffer_ptr: Cronet_BufferPtr,
     bytes_read: u64,
 ) {
-    let mut lockedMap = URL_REQUEST_CALLBACK_CALLBACKS.map().lock().unwrap();
-    let callback = lockedMap.get_mut(&self_ptr).unwrap();
-    callback.on_read_completed(
-        UrlRequestCallback { ptr: self_ptr },
-        UrlRequest { ptr: request_ptr },
-        UrlResponseInfo { ptr: info_ptr },
-        Buffer { ptr: buffer_ptr },
-        bytes_read,
-    );
+    dispatch(self_ptr, request_ptr, |callback| {
+        callback.on_read_completed(
+            Borrowed::new(UrlRequestCallback { ptr: self_ptr }),
+            Borrowed::new(UrlRequest { ptr: request_ptr }),
+            Borrowed::new(UrlResponseInfo { ptr: info_ptr }),
+            // Cronet hands ownership of the buffer back to the app.
+            Buffer { ptr: buffer_ptr },
+            bytes_read,
+        );
+    });
 }
 
 #[no_mangle]
@@ -72,13 +106,13 @@ unsafe extern "C" fn cronetUrlRequestCallbackOnSucceeded(
     request_ptr: Cronet_UrlRequestPtr,
     info_ptr: Cronet_UrlResponseInfoPtr,
 ) {
-    let mut lockedMap = URL_REQUEST_CALLBACK_CALLBACKS.map().lock().unwrap();
-    let callback = lockedMap.get_mut(&self_ptr).unwrap();
-    callback.on_succeeded(
-        UrlRequestCallback { ptr: self_ptr },
-        UrlRequest { ptr: request_ptr },
-        UrlResponseInfo { ptr: info_ptr },
-    );
+    dispatch(self_ptr, request_ptr, |callback| {
+        callback.on_succeeded(
+            Borrowed::new(UrlRequestCallback { ptr: self_ptr }),
+            Borrowed::new(UrlRequest { ptr: request_ptr }),
+            Borrowed::new(UrlResponseInfo { ptr: info_ptr }),
+        );
+    });
 }
 
 #[no_mangle]
@@ -88,14 +122,14 @@ unsafe extern "C" fn cronetUrlRequestCallbackOnFailed(
     info_ptr: Cronet_UrlResponseInfoPtr,
     error_ptr: Cronet_ErrorPtr,
 ) {
-    let mut lockedMap = URL_REQUEST_CALLBACK_CALLBACKS.map().lock().unwrap();
-    let callback = lockedMap.get_mut(&self_ptr).unwrap();
-    callback.on_failed(
-        UrlRequestCallback { ptr: self_ptr },
-        UrlRequest { ptr: request_ptr },
-        UrlResponseInfo { ptr: info_ptr },
-        CronetError { ptr: error_ptr },
-    );
+    dispatch(self_ptr, request_ptr, |callback| {
+        callback.on_failed(
+            Borrowed::new(UrlRequestCallback { ptr: self_ptr }),
+            Borrowed::new(UrlRequest { ptr: request_ptr }),
+            response_info(info_ptr),
+            Borrowed::new(CronetError { ptr: error_ptr }),
+        );
+    });
 }
 
 #[no_mangle]
@@ -104,15 +138,16 @@ unsafe extern "C" fn cronetUrlRequestCallbackOnCanceled(
     request_ptr: Cronet_UrlRequestPtr,
     info_ptr: Cronet_UrlResponseInfoPtr,
 ) {
-    let mut lockedMap = URL_REQUEST_CALLBACK_CALLBACKS.map().lock().unwrap();
-    let callback = lockedMap.get_mut(&self_ptr).unwrap();
-    callback.on_canceled(
-        UrlRequestCallback { ptr: self_ptr },
-        UrlRequest { ptr: request_ptr },
-        UrlResponseInfo { ptr: info_ptr },
-    );
+    dispatch(self_ptr, request_ptr, |callback| {
+        callback.on_canceled(
+            Borrowed::new(UrlRequestCallback { ptr: self_ptr }),
+            Borrowed::new(UrlRequest { ptr: request_ptr }),
+            response_info(info_ptr),
+        );
+    });
 }
 
+/// The handles passed to the handler are only borrowed for the duration of each callback.
 pub struct UrlRequestCallback {
     pub(crate) ptr: Cronet_UrlRequestCallbackPtr,
 }
@@ -133,26 +168,26 @@ impl UrlRequestCallback {
                 Some(cronetUrlRequestCallbackOnFailed),
                 Some(cronetUrlRequestCallbackOnCanceled),
             );
-            URL_REQUEST_CALLBACK_CALLBACKS
-                .map()
-                .lock()
-                .unwrap()
-                .insert(ptr, Box::new(handler));
+            let handler: UrlRequestCallbackHandlerBox = Mutex::new(Box::new(handler));
+            Cronet_UrlRequestCallback_SetClientContext(ptr, client_context::into_raw(handler));
             Self { ptr }
         }
     }
 }
 
-impl Destroy for UrlRequestCallback {
-    fn destroy(&self) {
+impl Drop for UrlRequestCallback {
+    fn drop(&mut self) {
         unsafe {
-            let mut lockedMap = URL_REQUEST_CALLBACK_CALLBACKS.map().lock().unwrap();
-            lockedMap.remove(&self.ptr);
+            client_context::release::<UrlRequestCallbackHandlerBox>(
+                Cronet_UrlRequestCallback_GetClientContext(self.ptr),
+            );
             Cronet_UrlRequestCallback_Destroy(self.ptr);
         }
     }
 }
 
+unsafe impl Send for UrlRequestCallback {}
+
 pub trait UrlRequestCallbackHandler {
     /// Invoked whenever a redirect is encountered. This will only be invoked
     /// between the call to `URLRequest::Start()` and
@@ -169,9 +204,9 @@ pub trait UrlRequestCallbackHandler {
     /// * `newLocationUrl`: Location where the request is redirected.
     fn on_redirect_received(
         &mut self,
-        url_request_callback: UrlRequestCallback,
-        request: UrlRequest,
-        info: UrlResponseInfo,
+        url_request_callback: Borrowed<'_, UrlRequestCallback>,
+        request: Borrowed<'_, UrlRequest>,
+        info: Borrowed<'_, UrlResponseInfo>,
         new_location_url: &str,
     );
 
@@ -190,9 +225,9 @@ pub trait UrlRequestCallbackHandler {
     /// * `info`: Response information.
     fn on_response_started(
         &mut self,
-        url_request_callback: UrlRequestCallback,
-        request: UrlRequest,
-        info: UrlResponseInfo,
+        url_request_callback: Borrowed<'_, UrlRequestCallback>,
+        request: Borrowed<'_, UrlRequest>,
+        info: Borrowed<'_, UrlResponseInfo>,
     );
 
     /// Invoked whenever part of the response body has been read. Only part of
@@ -215,9 +250,9 @@ pub trait UrlRequestCallbackHandler {
     /// * `bytesRead`: The number of bytes read into the `buffer`.
     fn on_read_completed(
         &mut self,
-        url_request_callback: UrlRequestCallback,
-        request: UrlRequest,
-        info: UrlResponseInfo,
+        url_request_callback: Borrowed<'_, UrlRequestCallback>,
+        request: Borrowed<'_, UrlRequest>,
+        info: Borrowed<'_, UrlResponseInfo>,
         buffer: Buffer,
         bytes_read: u64,
     );
@@ -225,8 +260,8 @@ pub trait UrlRequestCallbackHandler {
     /// Invoked when the request is completed successfully. Once invoked, no other
     /// `URLRequestCallbackHandler` methods will be invoked.
     ///
-    /// Implementations of `OnSucceeded()` are allowed to call
-    /// `URLRequest::Destroy()`, but note that destroying the request also destroys `info`.
+    /// Implementations of `OnSucceeded()` are allowed to drop the
+    /// owned [UrlRequest], but note that dropping the request also destroys `info`.
     ///
     /// Arguments:
     ///
@@ -234,17 +269,17 @@ pub trait UrlRequestCallbackHandler {
     /// * `info`: Response information. NOTE: this is owned by the request.
     fn on_succeeded(
         &mut self,
-        url_request_callback: UrlRequestCallback,
-        request: UrlRequest,
-        info: UrlResponseInfo,
+        url_request_callback: Borrowed<'_, UrlRequestCallback>,
+        request: Borrowed<'_, UrlRequest>,
+        info: Borrowed<'_, UrlResponseInfo>,
     );
 
     /// Invoked if the request failed for any reason after `URLRequest::Start()`.
     /// Once invoked, no other `URLRequestCallbackHandler` methods will be invoked.
     /// `error` provides information about the failure.
     ///
-    /// Implementations of `URLRequestCallbackHandler::OnFailed` are allowed to call
-    /// `URLRequest::Destroy()`, but note that destroying the request also destroys `info` and `error`.
+    /// Implementations of `URLRequestCallbackHandler::OnFailed` are allowed to drop the
+    /// owned [UrlRequest], but note that dropping the request also destroys `info` and `error`.
     ///
     /// Arguments:
     ///
@@ -253,17 +288,17 @@ pub trait UrlRequestCallbackHandler {
     /// * `error`: Information about the error. NOTE: this is owned by the request.
     fn on_failed(
         &mut self,
-        url_request_callback: UrlRequestCallback,
-        request: UrlRequest,
-        info: UrlResponseInfo,
-        error: CronetError,
+        url_request_callback: Borrowed<'_, UrlRequestCallback>,
+        request: Borrowed<'_, UrlRequest>,
+        info: Option<Borrowed<'_, UrlResponseInfo>>,
+        error: Borrowed<'_, CronetError>,
     );
 
     /// Invoked if the request was canceled via `URLRequest::Cancel()`. Once
     /// invoked, no other `UrlRequestCallback` methods will be invoked.
     ///
-    /// Implementations of `URLRequestCallbackHandler::OnCanceled` are allowed to call
-    /// `URLRequest::Destroy()`, but note that destroying the request also destroys `info` and `error`.
+    /// Implementations of `URLRequestCallbackHandler::OnCanceled` are allowed to drop the
+    /// owned [UrlRequest], but note that dropping the request also destroys `info` and `error`.
     ///
     /// Arguments:
     ///
@@ -271,16 +306,35 @@ pub trait UrlRequestCallbackHandler {
     /// * `info`: Response information. May be `None` if no response was received. NOTE: this is owned by the request.
     fn on_canceled(
         &mut self,
-        url_request_callback: UrlRequestCallback,
-        request: UrlRequest,
-        info: UrlResponseInfo,
+        url_request_callback: Borrowed<'_, UrlRequestCallback>,
+        request: Borrowed<'_, UrlRequest>,
+        info: Option<Borrowed<'_, UrlResponseInfo>>,
     );
+
+    /// Invoked if one of the other methods panicked, as panics must not unwind into Cronet.
+    /// The panic may have left the handler in an inconsistent state.
+    ///
+    /// The default implementation cancels the request, which has no effect if it is already done.
+    ///
+    /// Arguments:
+    ///
+    /// * `request`: Request whose callback panicked.
+    /// * `message`: The panic message.
+    fn on_panicked(
+        &mut self,
+        _url_request_callback: Borrowed<'_, UrlRequestCallback>,
+        request: Borrowed<'_, UrlRequest>,
+        _message: String,
+    ) {
+        request.cancel();
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        Buffer, Destroy, UrlRequest, UrlRequestCallback, UrlRequestCallbackHandler, UrlResponseInfo,
+        Borrowed, Buffer, UrlRequest, UrlRequestCallback, UrlRequestCallbackHandler,
+        UrlResponseInfo,
     };
 
     struct TestUrlRequestCallbackHandler;
@@ -288,9 +342,9 @@ mod tests {
     impl UrlRequestCallbackHandler for TestUrlRequestCallbackHandler {
         fn on_redirect_received(
             &mut self,
-            _: UrlRequestCallback,
-            _: UrlRequest,
-            _: UrlResponseInfo,
+            _: Borrowed<'_, UrlRequestCallback>,
+            _: Borrowed<'_, UrlRequest>,
+            _: Borrowed<'_, UrlResponseInfo>,
             _: &str,
         ) {
             println!("on_redirect_received");
@@ -298,39 +352,49 @@ mod tests {
 
         fn on_response_started(
             &mut self,
-            _: UrlRequestCallback,
-            _: UrlRequest,
-            _: UrlResponseInfo,
+            _: Borrowed<'_, UrlRequestCallback>,
+            _: Borrowed<'_, UrlRequest>,
+            _: Borrowed<'_, UrlResponseInfo>,
         ) {
             println!("on_response_started")
         }
 
         fn on_read_completed(
             &mut self,
-            _: UrlRequestCallback,
-            _: UrlRequest,
-            _: UrlResponseInfo,
+            _: Borrowed<'_, UrlRequestCallback>,
+            _: Borrowed<'_, UrlRequest>,
+            _: Borrowed<'_, UrlResponseInfo>,
             _: Buffer,
             _: u64,
         ) {
             println!("on_read_completed");
         }
 
-        fn on_succeeded(&mut self, _: UrlRequestCallback, _: UrlRequest, _: UrlResponseInfo) {
+        fn on_succeeded(
+            &mut self,
+            _: Borrowed<'_, UrlRequestCallback>,
+            _: Borrowed<'_, UrlRequest>,
+            _: Borrowed<'_, UrlResponseInfo>,
+        ) {
             println!("on_succeeded");
         }
 
         fn on_failed(
             &mut self,
-            _: UrlRequestCallback,
-            _: UrlRequest,
-            _: UrlResponseInfo,
-            _: crate::CronetError,
+            _: Borrowed<'_, UrlRequestCallback>,
+            _: Borrowed<'_, UrlRequest>,
+            _: Option<Borrowed<'_, UrlResponseInfo>>,
+            _: Borrowed<'_, crate::CronetError>,
         ) {
             println!("on_failed");
         }
 
-        fn on_canceled(&mut self, _: UrlRequestCallback, _: UrlRequest, _: UrlResponseInfo) {
+        fn on_canceled(
+            &mut self,
+            _: Borrowed<'_, UrlRequestCallback>,
+            _: Borrowed<'_, UrlRequest>,
+            _: Option<Borrowed<'_, UrlResponseInfo>>,
+        ) {
             println!("on_canceled");
         }
     }
@@ -339,7 +403,6 @@ mod tests {
     fn test_url_request_callback() {
         // TODO: test handler methods
         let handler = TestUrlRequestCallbackHandler;
-        let url_request_callback = UrlRequestCallback::new(handler);
-        url_request_callback.destroy();
+        let _url_request_callback = UrlRequestCallback::new(handler);
     }
 }
@@ -1,10 +1,12 @@
-use std::ffi::{CStr, CString};
+use std::{borrow::Cow, ffi::CString};
 
+#[cfg(feature = "client")]
 use http::{request::Parts, Request};
 
+#[cfg(feature = "client")]
+use crate::client::{Body, BodyUploadDataProvider};
 use crate::{
-    client::{Body, BodyUploadDataProvider},
-    Annotation, Cronet_UrlRequestParamsPtr, Cronet_UrlRequestParams_Create,
+    string, Annotation, Borrowed, Cronet_UrlRequestParamsPtr, Cronet_UrlRequestParams_Create,
     Cronet_UrlRequestParams_Destroy, Cronet_UrlRequestParams_REQUEST_PRIORITY,
     Cronet_UrlRequestParams_allow_direct_executor_get,
     Cronet_UrlRequestParams_allow_direct_executor_set, Cronet_UrlRequestParams_annotations_add,
@@ -22,7 +24,7 @@ use crate::{
     Cronet_UrlRequestParams_upload_data_provider_executor_get,
     Cronet_UrlRequestParams_upload_data_provider_executor_set,
     Cronet_UrlRequestParams_upload_data_provider_get,
-    Cronet_UrlRequestParams_upload_data_provider_set, Destroy, Executor, HttpHeader,
+    Cronet_UrlRequestParams_upload_data_provider_set, Executor, HttpHeader,
     RequestFinishedInfoListener, UploadDataProvider,
 };
 
@@ -53,21 +55,15 @@ impl UrlRequestParams {
         }
     }
 
-    pub fn method(&self) -> &str {
-        unsafe {
-            let c_str = Cronet_UrlRequestParams_http_method_get(self.ptr);
-            let c_str = CStr::from_ptr(c_str);
-            let str_slice = c_str.to_str().unwrap();
-            str_slice
-        }
+    pub fn method(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_UrlRequestParams_http_method_get(self.ptr)) }
     }
 
     /// Add an HTTP header to this request.
-    /// Note that the header you provide is destroyed after it has been added to the request.
-    pub fn add_header(&self, header: HttpHeader) {
+    /// The header is copied, so it can be dropped afterwards.
+    pub fn add_header(&self, header: &HttpHeader) {
         unsafe {
             Cronet_UrlRequestParams_request_headers_add(self.ptr, header.ptr);
-            header.destroy();
         }
     }
 
@@ -75,10 +71,10 @@ impl UrlRequestParams {
         unsafe { Cronet_UrlRequestParams_request_headers_size(self.ptr) }
     }
 
-    pub fn header_at(&self, index: u32) -> HttpHeader {
+    pub fn header_at(&self, index: u32) -> Borrowed<'_, HttpHeader> {
         unsafe {
             let header_ptr = Cronet_UrlRequestParams_request_headers_at(self.ptr, index);
-            HttpHeader { ptr: header_ptr }
+            Borrowed::new(HttpHeader { ptr: header_ptr })
         }
     }
 
@@ -119,16 +115,19 @@ impl UrlRequestParams {
     /// Upload data provider.
     /// Setting this value switches the method to `POST` if not explicitly set.
     /// Starting the request will fail if a `Content-Type` header is not set.
+    ///
+    /// Cronet takes ownership of the provider, which is dropped once the request has closed it.
+    /// A provider that is never used by a request is leaked.
     pub fn set_upload_data_provider(&self, provider: UploadDataProvider) {
         unsafe {
-            Cronet_UrlRequestParams_upload_data_provider_set(self.ptr, provider.ptr);
+            Cronet_UrlRequestParams_upload_data_provider_set(self.ptr, provider.into_raw());
         }
     }
 
-    pub fn upload_data_provider(&self) -> UploadDataProvider {
+    pub fn upload_data_provider(&self) -> Borrowed<'_, UploadDataProvider> {
         unsafe {
             let ptr = Cronet_UrlRequestParams_upload_data_provider_get(self.ptr);
-            UploadDataProvider { ptr }
+            Borrowed::new(UploadDataProvider { ptr })
         }
     }
 
@@ -137,10 +136,10 @@ impl UrlRequestParams {
         unsafe { Cronet_UrlRequestParams_upload_data_provider_executor_set(self.ptr, executor.ptr) }
     }
 
-    pub fn upload_data_executor(&self) -> Executor {
+    pub fn upload_data_executor(&self) -> Borrowed<'_, Executor> {
         unsafe {
             let ptr = Cronet_UrlRequestParams_upload_data_provider_executor_get(self.ptr);
-            Executor { ptr }
+            Borrowed::new(Executor { ptr })
         }
     }
 
@@ -199,11 +198,11 @@ impl UrlRequestParams {
     /// call to the listener may happen after a [crate::UrlRequestCallbackHandler] method is called.
     ///
     /// Assuming the listener won't run again (there are no pending requests with the listener attached, either via Engine or `URLRequest`),
-    /// the app may destroy it once its [crate::OnRequestFinishedFn] has started,
+    /// the app may drop it once its [crate::OnRequestFinishedFn] has started,
     /// even inside that method.
     pub fn set_request_finished_listener(
         &self,
-        request_finished_listener: RequestFinishedInfoListener,
+        request_finished_listener: &RequestFinishedInfoListener,
     ) {
         unsafe {
             Cronet_UrlRequestParams_request_finished_listener_set(
@@ -213,31 +212,31 @@ impl UrlRequestParams {
         }
     }
 
-    pub fn request_finished_listener(&self) -> RequestFinishedInfoListener {
+    pub fn request_finished_listener(&self) -> Borrowed<'_, RequestFinishedInfoListener> {
         unsafe {
             let ptr = Cronet_UrlRequestParams_request_finished_listener_get(self.ptr);
-            RequestFinishedInfoListener { ptr }
+            Borrowed::new(RequestFinishedInfoListener { ptr })
         }
     }
 
     /// Sets the [Executor] used to run the [RequestFinishedInfoListener].
     ///
-    /// Similar to [RequestFinishedInfoListener], the app may destroy `RequestFinishedExecutor` in or after [crate::OnRequestFinishedFn].
+    /// Similar to [RequestFinishedInfoListener], the app may drop `RequestFinishedExecutor` in or after [crate::OnRequestFinishedFn].
     ///
-    /// It's also okay to destroy `RequestFinishedExecutor` in or after one
+    /// It's also okay to drop `RequestFinishedExecutor` in or after one
     /// of `UrlRequestCallbackHandler::on_canceled()`, `UrlRequestCallbackHandler::on_failed()` or `UrlRequestCallbackHandler::on_succeeded()`.
     ///
     /// Of course, both of these are only true if `request_finished_executor` isn't being used for anything else that might start running in the future.
-    pub fn set_request_finished_executor(&self, executor: Executor) {
+    pub fn set_request_finished_executor(&self, executor: &Executor) {
         unsafe {
             Cronet_UrlRequestParams_request_finished_executor_set(self.ptr, executor.ptr);
         }
     }
 
-    pub fn request_finished_executor(&self) -> Executor {
+    pub fn request_finished_executor(&self) -> Borrowed<'_, Executor> {
         unsafe {
             let ptr = Cronet_UrlRequestParams_request_finished_executor_get(self.ptr);
-            Executor { ptr }
+            Borrowed::new(Executor { ptr })
         }
     }
 
@@ -262,8 +261,8 @@ impl UrlRequestParams {
     }
 }
 
-impl Destroy for UrlRequestParams {
-    fn destroy(&self) {
+impl Drop for UrlRequestParams {
+    fn drop(&mut self) {
         unsafe {
             Cronet_UrlRequestParams_Destroy(self.ptr);
         }
@@ -293,13 +292,14 @@ where
         for (name, value) in &headers {
             let header = HttpHeader::new();
             header.set_name(name.as_str());
-            header.set_value(value.to_str().unwrap());
-            request_parameters.add_header(header);
+            header.set_value_bytes(value.as_bytes());
+            request_parameters.add_header(&header);
         }
 
         let body: Body = body.into();
-        if body.len().unwrap_or(0) > 0 {
-            let body_handler = BodyUploadDataProvider::new(body, None); // TODO: support rewind
+        // Bodies of unknown length are uploaded in chunks.
+        if body.len() != Some(0) {
+            let body_handler = BodyUploadDataProvider::from(body);
             let upload_data_provider = UploadDataProvider::new(body_handler);
             request_parameters.set_upload_data_provider(upload_data_provider);
         }
@@ -373,26 +373,34 @@ impl TryFrom<u32> for Idempotency {
 mod tests {
     use crate::{
         url_request_params::{Idempotency, RequestPriority},
-        Annotation, Buffer, Destroy, Executor, HttpHeader, RequestFinishedInfoListener,
+        Annotation, Borrowed, Buffer, Executor, HttpHeader, RequestFinishedInfoListener,
         UploadDataProvider, UploadDataProviderHandler, UploadDataSink, UrlRequestParams,
     };
 
     struct TestUploadDataProviderHandler;
 
     impl UploadDataProviderHandler for TestUploadDataProviderHandler {
-        fn length(&self, _: UploadDataProvider) -> i64 {
+        fn length(&self, _: Borrowed<'_, UploadDataProvider>) -> i64 {
             10
         }
 
-        fn read(&self, _: UploadDataProvider, _: UploadDataSink, _: Buffer) {}
+        fn read(
+            &mut self,
+            _: Borrowed<'_, UploadDataProvider>,
+            _: Borrowed<'_, UploadDataSink>,
+            _: Borrowed<'_, Buffer>,
+        ) {
+        }
 
-        fn rewind(&mut self, _: UploadDataProvider, sink: UploadDataSink) {
+        fn rewind(
+            &mut self,
+            _: Borrowed<'_, UploadDataProvider>,
+            sink: Borrowed<'_, UploadDataSink>,
+        ) {
             sink.on_rewind_succeeded();
         }
 
-        fn close(&self, upload_data_provider: UploadDataProvider) {
-            upload_data_provider.destroy();
-        }
+        fn close(&self, _: Borrowed<'_, UploadDataProvider>) {}
     }
 
     #[test]
@@ -403,7 +411,7 @@ mod tests {
         let http_header = HttpHeader::new();
         http_header.set_name("User-Agent");
         http_header.set_value("Cronet");
-        url_request_params.add_header(http_header);
+        url_request_params.add_header(&http_header);
         assert_eq!(url_request_params.header_size(), 1);
         let http_header = url_request_params.header_at(0);
         assert_eq!(http_header.name(), "User-Agent");
@@ -411,37 +419,28 @@ mod tests {
         url_request_params.clear_headers();
         assert_eq!(url_request_params.header_size(), 0);
         url_request_params.set_disable_cache(true);
-        assert_eq!(url_request_params.disable_cache(), true);
+        assert!(url_request_params.disable_cache());
         url_request_params.set_priority(RequestPriority::Lowest);
         assert_eq!(url_request_params.priority(), RequestPriority::Lowest);
         let upload_data_provider = UploadDataProvider::new(TestUploadDataProviderHandler);
         url_request_params.set_upload_data_provider(upload_data_provider);
         assert_eq!(url_request_params.upload_data_provider().length(), 10);
-        url_request_params.set_upload_data_executor(&Executor::new(|_, _| {}));
-        assert_eq!(
-            url_request_params.upload_data_executor().ptr.is_null(),
-            false
-        );
+        let executor = Executor::new(|_, _| {});
+        url_request_params.set_upload_data_executor(&executor);
+        assert!(!url_request_params.upload_data_executor().ptr.is_null());
         url_request_params.set_allow_direct_executor(true);
-        assert_eq!(url_request_params.allow_direct_executor(), true);
+        assert!(url_request_params.allow_direct_executor());
         let annotation = Annotation::default();
         url_request_params.add_annotation(annotation);
         assert_eq!(url_request_params.annotations_size(), 1);
         url_request_params.clear_annotations();
         assert_eq!(url_request_params.annotations_size(), 0);
-        url_request_params
-            .set_request_finished_listener(RequestFinishedInfoListener::new(|_, _, _, _| {}));
-        assert_eq!(
-            url_request_params.request_finished_listener().ptr.is_null(),
-            false
-        );
-        url_request_params.set_request_finished_executor(Executor::new(|_, _| {}));
-        assert_eq!(
-            url_request_params.request_finished_executor().ptr.is_null(),
-            false
-        );
+        let listener = RequestFinishedInfoListener::new(|_, _, _, _| {});
+        url_request_params.set_request_finished_listener(&listener);
+        assert!(!url_request_params.request_finished_listener().ptr.is_null());
+        url_request_params.set_request_finished_executor(&executor);
+        assert!(!url_request_params.request_finished_executor().ptr.is_null());
         url_request_params.set_idempotency(Idempotency::Idempotent);
         assert_eq!(url_request_params.idempotency(), Idempotency::Idempotent);
-        url_request_params.destroy();
     }
 }
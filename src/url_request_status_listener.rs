@@ -1,53 +1,76 @@
-use once_cell::sync::Lazy;
+use std::{mem, sync::Mutex};
 
 use crate::{
-    state::CronetCallbacks, Cronet_UrlRequestStatusListenerPtr,
+    client_context, unwind, Borrowed, Cronet_UrlRequestStatusListenerPtr,
     Cronet_UrlRequestStatusListener_CreateWith, Cronet_UrlRequestStatusListener_Destroy,
-    Cronet_UrlRequestStatusListener_Status, Destroy,
+    Cronet_UrlRequestStatusListener_GetClientContext,
+    Cronet_UrlRequestStatusListener_SetClientContext, Cronet_UrlRequestStatusListener_Status,
 };
 
-static mut URL_REQUEST_STATUS_LISTENER_CALLBACKS: Lazy<
-    CronetCallbacks<Cronet_UrlRequestStatusListenerPtr, UrlRequestStatusListenerOnStatusFn>,
-> = Lazy::new(CronetCallbacks::new);
+type UrlRequestStatusListenerHandler = Mutex<Box<UrlRequestStatusListenerOnStatusFn>>;
 
 #[no_mangle]
 unsafe extern "C" fn cronetUrlRequestStatusListenerOnStatus(
     self_ptr: Cronet_UrlRequestStatusListenerPtr,
     status_ptr: Cronet_UrlRequestStatusListener_Status,
 ) {
-    let lockedMap = URL_REQUEST_STATUS_LISTENER_CALLBACKS.map().lock().unwrap();
-    if let Some(callback) = lockedMap.get(&self_ptr) {
-        callback(
-            UrlRequestStatusListener { ptr: self_ptr },
-            UrlRequestStatus::try_from(status_ptr).unwrap(),
-        );
+    // The listener was handed over by `UrlRequest::status` and is only invoked once.
+    let listener = UrlRequestStatusListener { ptr: self_ptr };
+    let callback = client_context::get::<UrlRequestStatusListenerHandler>(
+        Cronet_UrlRequestStatusListener_GetClientContext(self_ptr),
+    );
+    if let Some(callback) = callback {
+        // The listener is only invoked once, so a panic is ignored.
+        let _ = unwind::catch(|| {
+            let mut callback = unwind::lock(&callback);
+            callback(
+                Borrowed::new(UrlRequestStatusListener { ptr: self_ptr }),
+                UrlRequestStatus::try_from(status_ptr).unwrap(),
+            );
+        });
     }
+    drop(listener);
 }
 
 pub type UrlRequestStatusListenerOnStatusFn =
-    fn(url_request_status_listener: UrlRequestStatusListener, status: UrlRequestStatus);
+    dyn FnMut(Borrowed<'_, UrlRequestStatusListener>, UrlRequestStatus) + Send;
 
 pub struct UrlRequestStatusListener {
     pub(crate) ptr: Cronet_UrlRequestStatusListenerPtr,
 }
 
 impl UrlRequestStatusListener {
-    pub fn new() -> Self {
+    pub fn new<F>(on_status: F) -> Self
+    where
+        F: FnMut(Borrowed<'_, UrlRequestStatusListener>, UrlRequestStatus) + Send + 'static,
+    {
         unsafe {
-            Self {
-                ptr: Cronet_UrlRequestStatusListener_CreateWith(Some(
-                    cronetUrlRequestStatusListenerOnStatus,
-                )),
-            }
+            let ptr = Cronet_UrlRequestStatusListener_CreateWith(Some(
+                cronetUrlRequestStatusListenerOnStatus,
+            ));
+            let handler: UrlRequestStatusListenerHandler = Mutex::new(Box::new(on_status));
+            Cronet_UrlRequestStatusListener_SetClientContext(
+                ptr,
+                client_context::into_raw(handler),
+            );
+            Self { ptr }
         }
     }
+
+    /// Gives up ownership of the native listener, e.g. to a [crate::UrlRequest].
+    pub(crate) fn into_raw(self) -> Cronet_UrlRequestStatusListenerPtr {
+        let ptr = self.ptr;
+        mem::forget(self);
+        ptr
+    }
 }
 
-impl Destroy for UrlRequestStatusListener {
-    fn destroy(&self) {
+impl Drop for UrlRequestStatusListener {
+    fn drop(&mut self) {
         unsafe {
-            let mut lockedMap = URL_REQUEST_STATUS_LISTENER_CALLBACKS.map().lock().unwrap();
-            lockedMap.remove(&self.ptr);
+            client_context::release::<UrlRequestStatusListenerHandler>(
+                Cronet_UrlRequestStatusListener_GetClientContext(self.ptr),
+            );
             Cronet_UrlRequestStatusListener_Destroy(self.ptr);
         }
     }
@@ -55,12 +78,12 @@ impl Destroy for UrlRequestStatusListener {
 
 impl Default for UrlRequestStatusListener {
     fn default() -> Self {
-        Self::new()
+        Self::new(|_, _| {})
     }
 }
 
 /// Enum representing the status of a [crate::UrlRequest].
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum UrlRequestStatus {
     /// This state indicates that the request is completed, canceled, or is not started.
     Invalid = -1,
@@ -175,12 +198,11 @@ impl TryFrom<i32> for UrlRequestStatus {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Destroy, UrlRequestStatusListener};
+    use crate::UrlRequestStatusListener;
 
     #[test]
     fn test_url_request_status_listener() {
-        let listener = UrlRequestStatusListener::new();
-        assert!(listener.ptr != std::ptr::null_mut());
-        listener.destroy();
+        let listener = UrlRequestStatusListener::new(|_, _| {});
+        assert!(!listener.ptr.is_null());
     }
 }
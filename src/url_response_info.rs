@@ -1,21 +1,24 @@
-use std::ffi::{CStr, CString};
+use std::{borrow::Cow, ffi::CString};
 
-use http::{HeaderValue, Response, StatusCode, Version};
+#[cfg(feature = "client")]
+use http::{header::HeaderName, HeaderValue, Response, StatusCode, Version};
 
+#[cfg(feature = "client")]
+use crate::client::{ResponseError, ResponseInfo};
 use crate::{
-    Cronet_UrlResponseInfoPtr, Cronet_UrlResponseInfo_Create, Cronet_UrlResponseInfo_Destroy,
-    Cronet_UrlResponseInfo_all_headers_list_add, Cronet_UrlResponseInfo_all_headers_list_at,
-    Cronet_UrlResponseInfo_all_headers_list_clear, Cronet_UrlResponseInfo_all_headers_list_size,
-    Cronet_UrlResponseInfo_http_status_code_get, Cronet_UrlResponseInfo_http_status_code_set,
-    Cronet_UrlResponseInfo_http_status_text_get, Cronet_UrlResponseInfo_http_status_text_set,
-    Cronet_UrlResponseInfo_negotiated_protocol_get, Cronet_UrlResponseInfo_negotiated_protocol_set,
-    Cronet_UrlResponseInfo_proxy_server_get, Cronet_UrlResponseInfo_proxy_server_set,
-    Cronet_UrlResponseInfo_received_byte_count_get, Cronet_UrlResponseInfo_received_byte_count_set,
-    Cronet_UrlResponseInfo_url_chain_add, Cronet_UrlResponseInfo_url_chain_at,
-    Cronet_UrlResponseInfo_url_chain_clear, Cronet_UrlResponseInfo_url_chain_size,
-    Cronet_UrlResponseInfo_url_get, Cronet_UrlResponseInfo_url_set,
-    Cronet_UrlResponseInfo_was_cached_get, Cronet_UrlResponseInfo_was_cached_set, Destroy,
-    HttpHeader,
+    string, Borrowed, Cronet_UrlResponseInfoPtr, Cronet_UrlResponseInfo_Create,
+    Cronet_UrlResponseInfo_Destroy, Cronet_UrlResponseInfo_all_headers_list_add,
+    Cronet_UrlResponseInfo_all_headers_list_at, Cronet_UrlResponseInfo_all_headers_list_clear,
+    Cronet_UrlResponseInfo_all_headers_list_size, Cronet_UrlResponseInfo_http_status_code_get,
+    Cronet_UrlResponseInfo_http_status_code_set, Cronet_UrlResponseInfo_http_status_text_get,
+    Cronet_UrlResponseInfo_http_status_text_set, Cronet_UrlResponseInfo_negotiated_protocol_get,
+    Cronet_UrlResponseInfo_negotiated_protocol_set, Cronet_UrlResponseInfo_proxy_server_get,
+    Cronet_UrlResponseInfo_proxy_server_set, Cronet_UrlResponseInfo_received_byte_count_get,
+    Cronet_UrlResponseInfo_received_byte_count_set, Cronet_UrlResponseInfo_url_chain_add,
+    Cronet_UrlResponseInfo_url_chain_at, Cronet_UrlResponseInfo_url_chain_clear,
+    Cronet_UrlResponseInfo_url_chain_size, Cronet_UrlResponseInfo_url_get,
+    Cronet_UrlResponseInfo_url_set, Cronet_UrlResponseInfo_was_cached_get,
+    Cronet_UrlResponseInfo_was_cached_set, HttpHeader,
 };
 
 pub struct UrlResponseInfo {
@@ -33,12 +36,8 @@ impl UrlResponseInfo {
 
     /// The URL the response is for.
     /// This is the URL after following redirects, so it may not be the originally requested URL
-    pub fn url(&self) -> &str {
-        unsafe {
-            let url = Cronet_UrlResponseInfo_url_get(self.ptr);
-            let url = CStr::from_ptr(url);
-            url.to_str().unwrap()
-        }
+    pub fn url(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_UrlResponseInfo_url_get(self.ptr)) }
     }
 
     pub fn set_url(&self, url: &str) {
@@ -56,12 +55,8 @@ impl UrlResponseInfo {
 
     /// The URL at the given index in the chain.
     /// The first entry is the originally requested URL; the following entries are redirects followed.
-    pub fn url_chain_at(&self, index: u32) -> &str {
-        unsafe {
-            let url = Cronet_UrlResponseInfo_url_chain_at(self.ptr, index);
-            let url = CStr::from_ptr(url);
-            url.to_str().unwrap()
-        }
+    pub fn url_chain_at(&self, index: u32) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_UrlResponseInfo_url_chain_at(self.ptr, index)) }
     }
 
     /// Add a URL to the chain.
@@ -93,12 +88,8 @@ impl UrlResponseInfo {
 
     /// The HTTP status text of the status line.
     /// For example, if the request received a "HTTP/1.1 200 OK" response, this method returns "OK".
-    pub fn status_text(&self) -> &str {
-        unsafe {
-            let text = Cronet_UrlResponseInfo_http_status_text_get(self.ptr);
-            let text = CStr::from_ptr(text);
-            text.to_str().unwrap()
-        }
+    pub fn status_text(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_UrlResponseInfo_http_status_text_get(self.ptr)) }
     }
 
     pub fn set_status_text(&self, text: &str) {
@@ -112,14 +103,15 @@ impl UrlResponseInfo {
         unsafe { Cronet_UrlResponseInfo_all_headers_list_size(self.ptr) }
     }
 
-    pub fn header_at(&self, index: u32) -> HttpHeader {
+    pub fn header_at(&self, index: u32) -> Borrowed<'_, HttpHeader> {
         unsafe {
             let ptr = Cronet_UrlResponseInfo_all_headers_list_at(self.ptr, index);
-            HttpHeader { ptr }
+            Borrowed::new(HttpHeader { ptr })
         }
     }
 
-    pub fn add_header(&self, header: HttpHeader) {
+    /// Adds a copy of `header` to the headers of the response.
+    pub fn add_header(&self, header: &HttpHeader) {
         unsafe {
             Cronet_UrlResponseInfo_all_headers_list_add(self.ptr, header.ptr);
         }
@@ -145,13 +137,8 @@ impl UrlResponseInfo {
     /// The protocol (for example 'quic/1+spdy/3') negotiated with the server.
     /// An empty string if no protocol was negotiated, the protocol is
     /// not known, or when using plain HTTP or HTTPS.
-    pub fn negotiated_protocol(&self) -> &'static str {
-        unsafe {
-            let protocol = Cronet_UrlResponseInfo_negotiated_protocol_get(self.ptr);
-            let protocol = CStr::from_ptr(protocol);
-            let protocol = protocol.to_str().unwrap();
-            protocol
-        }
+    pub fn negotiated_protocol(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_UrlResponseInfo_negotiated_protocol_get(self.ptr)) }
     }
 
     pub fn set_negotiated_protocol(&self, protocol: &str) {
@@ -162,12 +149,8 @@ impl UrlResponseInfo {
     }
 
     /// The proxy server that was used for the request.
-    pub fn proxy_server(&self) -> &'static str {
-        unsafe {
-            let server = Cronet_UrlResponseInfo_proxy_server_get(self.ptr);
-            let server = CStr::from_ptr(server).to_str().unwrap();
-            server
-        }
+    pub fn proxy_server(&self) -> Cow<'_, str> {
+        unsafe { string::to_str(Cronet_UrlResponseInfo_proxy_server_get(self.ptr)) }
     }
 
     pub fn set_proxy_server(&self, proxy: &str) {
@@ -189,8 +172,8 @@ impl UrlResponseInfo {
     }
 }
 
-impl Destroy for UrlResponseInfo {
-    fn destroy(&self) {
+impl Drop for UrlResponseInfo {
+    fn drop(&mut self) {
         unsafe {
             Cronet_UrlResponseInfo_Destroy(self.ptr);
         }
@@ -204,48 +187,61 @@ impl Default for UrlResponseInfo {
 }
 
 #[cfg(feature = "client")]
-#[allow(clippy::from_over_into)]
-impl<T> Into<Response<T>> for UrlResponseInfo
+impl<T> TryFrom<&UrlResponseInfo> for Response<T>
 where
     T: Default,
 {
-    fn into(self) -> Response<T> {
+    type Error = ResponseError;
+
+    fn try_from(info: &UrlResponseInfo) -> Result<Self, Self::Error> {
         let mut response = Response::default();
 
         // Set HTTP version
-        let version = match self.negotiated_protocol() {
-            "http/0.9" => Version::HTTP_09,
-            "http/1.0" => Version::HTTP_10,
-            "http/1.1" => Version::HTTP_11,
-            "h2" => Version::HTTP_2,
-            "h3" => Version::HTTP_3,
-            "" => Version::HTTP_11,
-            version => panic!("Server responded with unknown HTTP version '{}'", version),
-        };
-        *response.version_mut() = version;
+        // The raw protocol is kept in the `ResponseInfo` extension.
+        *response.version_mut() = http_version(&info.negotiated_protocol());
 
         // Set status code
-        let status_code = self.status_code();
-        *response.status_mut() = StatusCode::from_u16(status_code as u16).unwrap();
-
-        // Set headers
-        let header_size = self.header_size();
+        let status_code = info.status_code();
+        *response.status_mut() = u16::try_from(status_code)
+            .ok()
+            .and_then(|code| StatusCode::from_u16(code).ok())
+            .ok_or(ResponseError::InvalidStatusCode(status_code))?;
+
+        // Set headers, keeping repeated headers in the order they were received
+        let header_size = info.header_size();
         for i in 0..header_size {
-            let header = self.header_at(i);
-            let name = header.name();
-            let value = header.value();
-            response
-                .headers_mut()
-                .insert(name, HeaderValue::from_static(value));
+            let header = info.header_at(i);
+            let name = HeaderName::from_bytes(header.name_bytes())
+                .map_err(|_| ResponseError::InvalidHeaderName(header.name().to_string()))?;
+            let value = HeaderValue::from_bytes(header.value_bytes())
+                .map_err(|_| ResponseError::InvalidHeaderValue(name.to_string()))?;
+            response.headers_mut().append(name, value);
         }
 
-        response
+        // Set metadata
+        response.extensions_mut().insert(ResponseInfo::from(info));
+
+        Ok(response)
+    }
+}
+
+/// Maps a negotiated protocol (ALPN) to the closest HTTP version.
+/// Unknown protocols are treated as HTTP/1.1.
+#[cfg(feature = "client")]
+fn http_version(protocol: &str) -> Version {
+    match protocol {
+        "http/0.9" => Version::HTTP_09,
+        "http/1.0" => Version::HTTP_10,
+        "h3" => Version::HTTP_3,
+        protocol if protocol.starts_with("h3-") || protocol.starts_with("quic") => Version::HTTP_3,
+        "h2" => Version::HTTP_2,
+        protocol if protocol.starts_with("h2-") || protocol.starts_with("spdy") => Version::HTTP_2,
+        _ => Version::HTTP_11,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Destroy;
 
     #[test]
     fn test_url_response_info() {
@@ -257,14 +253,13 @@ mod tests {
         url_response_info.set_status_text("OK");
         assert_eq!(url_response_info.status_text(), "OK");
         url_response_info.set_cached(true);
-        assert_eq!(url_response_info.cached(), true);
+        assert!(url_response_info.cached());
         url_response_info.set_negotiated_protocol("quic/1+spdy/3");
         assert_eq!(url_response_info.negotiated_protocol(), "quic/1+spdy/3");
         url_response_info.set_proxy_server("proxy");
         assert_eq!(url_response_info.proxy_server(), "proxy");
         url_response_info.set_received_byte_count(100);
         assert_eq!(url_response_info.received_byte_count(), 100);
-        url_response_info.destroy();
     }
 
     #[test]
@@ -275,7 +270,6 @@ mod tests {
         assert_eq!(url_response_info.url_chain_at(0), "https://www.google.com");
         url_response_info.clear_url_chain();
         assert_eq!(url_response_info.url_chain_size(), 0);
-        url_response_info.destroy();
     }
 
     #[test]
@@ -284,13 +278,79 @@ mod tests {
         let header = crate::HttpHeader::new();
         header.set_name("name");
         header.set_value("value");
-        url_response_info.add_header(header);
+        url_response_info.add_header(&header);
         assert_eq!(url_response_info.header_size(), 1);
         let header2 = url_response_info.header_at(0);
         assert_eq!(header2.name(), "name");
         assert_eq!(header2.value(), "value");
         url_response_info.clear_headers();
         assert_eq!(url_response_info.header_size(), 0);
-        url_response_info.destroy();
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn it_keeps_repeated_headers() {
+        let url_response_info = super::UrlResponseInfo::new();
+        url_response_info.set_status_code(200);
+        for (name, value) in [
+            ("set-cookie", "a=1"),
+            ("vary", "accept"),
+            ("set-cookie", "b=2"),
+        ] {
+            let header = crate::HttpHeader::new();
+            header.set_name(name);
+            header.set_value(value);
+            url_response_info.add_header(&header);
+        }
+
+        let response = http::Response::<()>::try_from(&url_response_info).unwrap();
+        let cookies: Vec<_> = response.headers().get_all("set-cookie").iter().collect();
+        assert_eq!(cookies, ["a=1", "b=2"]);
+        assert_eq!(response.headers()["vary"], "accept");
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn it_rejects_invalid_headers() {
+        let url_response_info = super::UrlResponseInfo::new();
+        url_response_info.set_status_code(200);
+        let header = crate::HttpHeader::new();
+        header.set_name("invalid name");
+        header.set_value("value");
+        url_response_info.add_header(&header);
+
+        let error = http::Response::<()>::try_from(&url_response_info).unwrap_err();
+        assert!(
+            matches!(error, crate::client::ResponseError::InvalidHeaderName(name) if name == "invalid name")
+        );
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn it_maps_unknown_protocols() {
+        use http::Version;
+
+        let url_response_info = super::UrlResponseInfo::new();
+        url_response_info.set_status_code(200);
+        url_response_info.set_negotiated_protocol("quic/1+spdy/3");
+        let response = http::Response::<()>::try_from(&url_response_info).unwrap();
+        assert_eq!(response.version(), Version::HTTP_3);
+
+        assert_eq!(super::http_version("spdy/3"), Version::HTTP_2);
+        assert_eq!(super::http_version("h3-29"), Version::HTTP_3);
+        assert_eq!(super::http_version(""), Version::HTTP_11);
+        assert_eq!(super::http_version("unknown"), Version::HTTP_11);
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn it_rejects_invalid_status_codes() {
+        let url_response_info = super::UrlResponseInfo::new();
+        url_response_info.set_status_code(1000);
+        let error = http::Response::<()>::try_from(&url_response_info).unwrap_err();
+        assert!(matches!(
+            error,
+            crate::client::ResponseError::InvalidStatusCode(1000)
+        ));
     }
 }